@@ -0,0 +1,90 @@
+#import bevy_pbr::mesh_functions as mesh_functions
+#import bevy_pbr::mesh_bindings mesh
+#import bevy_pbr::mesh_view_bindings view, fog, globals
+#import bevy_pbr::mesh_view_types FOG_MODE_OFF
+#import bevy_pbr::mesh_vertex_output MeshVertexOutput
+#import bevy_pbr::pbr_functions as pbr_functions
+#import bevy_pbr::pbr_types STANDARD_MATERIAL_FLAGS_ALPHA_MODE_BLEND
+#import bevy_core_pipeline::tonemapping tone_mapping
+
+// Keep in sync with `WaterMaterial` in src/world/water.rs.
+struct WaterMaterial {
+    color: vec4<f32>,
+    wave_height: f32,
+    wave_length: f32,
+    wave_speed: f32,
+};
+
+@group(1) @binding(0)
+var<uniform> material: WaterMaterial;
+
+struct Vertex {
+    @location(0) position: vec3<f32>,
+};
+
+const TAU: f32 = 6.28318530718;
+
+// Height of the ripples at `position` on the ground and their slope along x and z.
+fn wave(position: vec2<f32>, seconds: f32) -> vec3<f32> {
+    let k = TAU / material.wave_length;
+    let offset = seconds * material.wave_speed;
+
+    let a = k * (position.x + offset);
+    let b = k * (position.y * 0.8 - offset * 0.6);
+    let amplitude = material.wave_height / 2.0;
+
+    return vec3(
+        amplitude * (sin(a) + sin(b)),
+        amplitude * k * cos(a),
+        amplitude * k * 0.8 * cos(b),
+    );
+}
+
+@vertex
+fn vertex(vertex: Vertex) -> MeshVertexOutput {
+    var out: MeshVertexOutput;
+
+    var world_position = mesh_functions::mesh_position_local_to_world(mesh.model, vec4(vertex.position, 1.0));
+    let ripple = wave(world_position.xz, globals.time);
+
+    world_position.y += ripple.x;
+
+    out.world_position = world_position;
+    out.position = mesh_functions::mesh_position_world_to_clip(world_position);
+    out.world_normal = normalize(vec3(-ripple.y, 1.0, -ripple.z));
+
+    return out;
+}
+
+@fragment
+fn fragment(
+    in: MeshVertexOutput,
+    @builtin(front_facing) is_front: bool,
+) -> @location(0) vec4<f32> {
+    var pbr_input = pbr_functions::pbr_input_new();
+
+    pbr_input.material.base_color = material.color;
+    pbr_input.material.perceptual_roughness = 0.1;
+    pbr_input.material.reflectance = 0.6;
+    pbr_input.material.flags = STANDARD_MATERIAL_FLAGS_ALPHA_MODE_BLEND;
+
+    pbr_input.frag_coord = in.position;
+    pbr_input.world_position = in.world_position;
+    pbr_input.world_normal = pbr_functions::prepare_world_normal(in.world_normal, false, is_front);
+    pbr_input.is_orthographic = view.projection[3].w == 1.0;
+    pbr_input.N = pbr_input.world_normal;
+    pbr_input.V = pbr_functions::calculate_view(in.world_position, pbr_input.is_orthographic);
+    pbr_input.flags = mesh.flags;
+
+    var color = pbr_functions::pbr(pbr_input);
+
+    if (fog.mode != FOG_MODE_OFF) {
+        color = pbr_functions::apply_fog(fog, color, in.world_position.xyz, view.world_position.xyz);
+    }
+
+#ifdef TONEMAP_IN_SHADER
+    color = tone_mapping(color, view.color_grading);
+#endif
+
+    return color;
+}
@@ -0,0 +1,143 @@
+use bevy::{app::AppExit, prelude::*};
+
+use crate::{
+    controls::{movement::ControlData, place_model::Orientation},
+    world::{
+        batching::TileRendering,
+        grid::Grid,
+        point::{Point, PointType, Position},
+        World,
+    },
+};
+
+/// Compares the two ways floor tiles can be drawn, see [`TileRendering`].
+///
+/// `cargo run --release -- --bench batched 200` fills a 200 by 200 map with roads and grass,
+/// circles the camera over it and prints the entity count and frame times before quitting.
+/// `scenes` in place of `batched` spawns a scene for every tile instead.
+pub struct BenchPlugin {
+    pub rendering: TileRendering,
+    pub size: i32,
+}
+
+impl BenchPlugin {
+    /// Frames left for the models to load and the chunks in view to spawn before measuring.
+    const WARMUP: u32 = 300;
+    const FRAMES: u32 = 600;
+
+    /// Reads `--bench [scenes|batched] [size]` from the command line, `None` without
+    /// `--bench`.
+    pub fn from_args() -> Option<Self> {
+        let args: Vec<String> = std::env::args().collect();
+        let at = args.iter().position(|arg| arg == "--bench")?;
+
+        let rendering = match args.get(at + 1).map(String::as_str) {
+            Some("scenes") => TileRendering::Scenes,
+            _ => TileRendering::Batched,
+        };
+
+        let size = args
+            .get(at + 2)
+            .and_then(|size| size.parse().ok())
+            .unwrap_or(200);
+
+        Some(Self { rendering, size })
+    }
+}
+
+impl Plugin for BenchPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(self.rendering);
+        app.insert_resource(Bench {
+            size: self.size,
+            frame: 0,
+            frame_times: vec![],
+        });
+        app.add_systems(Startup, fill_map);
+        app.add_systems(Update, run_bench);
+    }
+}
+
+#[derive(Resource)]
+struct Bench {
+    size: i32,
+    frame: u32,
+    frame_times: Vec<f32>,
+}
+
+/// Grass with a road every few cells both ways.
+fn fill_map(bench: Res<Bench>, mut world: ResMut<World>) {
+    let half = bench.size / 2;
+
+    for x in 0..bench.size {
+        for y in 0..bench.size {
+            let (has, facing) = match (x % 6 == 0, y % 6 == 0) {
+                (true, true) => (PointType::RoadIntersection, 0),
+                (true, false) => (PointType::RoadStraight, 0),
+                (false, true) => (PointType::RoadStraight, 1),
+                (false, false) => (PointType::Grass, 0),
+            };
+
+            world.insert(Point::new(
+                has,
+                Position::new(x - half, y - half),
+                Orientation::index(facing),
+            ));
+        }
+    }
+}
+
+fn run_bench(
+    time: Res<Time>,
+    grid: Res<Grid>,
+    rendering: Res<TileRendering>,
+    mut bench: ResMut<Bench>,
+    mut control_data: ResMut<ControlData>,
+    entities: Query<Entity>,
+    points: Query<(), With<Point>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    bench.frame += 1;
+
+    // Circles over the map zoomed all the way out, so chunks keep streaming in and out.
+    let angle = bench.frame as f32 * 0.004;
+    let reach = bench.size as f32 * grid.cell_size * 0.3;
+
+    control_data.pivot = Vec3::new(angle.cos(), 0., angle.sin()) * reach;
+    control_data.radius = control_data.max_radius;
+
+    if bench.frame <= BenchPlugin::WARMUP {
+        return;
+    }
+
+    let frame_time = time.delta_seconds() * 1000.;
+    bench.frame_times.push(frame_time);
+
+    if bench.frame < BenchPlugin::WARMUP + BenchPlugin::FRAMES {
+        return;
+    }
+
+    let mut times = bench.frame_times.clone();
+    times.sort_by(f32::total_cmp);
+
+    let mean = times.iter().sum::<f32>() / times.len() as f32;
+    let p95 = times[times.len() * 95 / 100];
+
+    println!(
+        "{:?} rendering, {} by {} tiles",
+        *rendering, bench.size, bench.size
+    );
+    println!(
+        "Entities {} ({} points spawned)",
+        entities.iter().count(),
+        points.iter().count()
+    );
+    println!(
+        "Frame time mean {:.2} ms, p95 {:.2} ms, max {:.2} ms",
+        mean,
+        p95,
+        times[times.len() - 1]
+    );
+
+    exit.send(AppExit);
+}
@@ -0,0 +1,277 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::world::{grid::Grid, point::Position, World};
+
+use super::{
+    input_map::{Action, Actions, Typing},
+    movement::{ControlData, Orbit},
+    selection::Selection,
+    walk::CameraView,
+};
+
+/// Saved camera positions and framing of the map.
+///
+/// `Ctrl` + a number stores the camera in that slot and `Alt` + the number flies back to
+/// it. Storing asks for a name, `Enter` keeps what was typed and `Escape` the name the slot
+/// had. `Home` frames the whole map and `Z` the selected cells.
+pub struct BookmarksPlugin;
+
+impl Plugin for BookmarksPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Bookmarks::default());
+        app.insert_resource(Naming::default());
+        app.add_systems(Startup, setup);
+        app.add_systems(
+            Update,
+            (bookmark_keys, frame_keys).run_if(in_state(CameraView::Orbit)),
+        );
+        app.add_systems(Update, type_name.after(bookmark_keys));
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub name: String,
+    pub slot: usize,
+    pub orbit: Orbit,
+}
+
+#[derive(Resource, Default, Debug)]
+pub struct Bookmarks {
+    pub list: Vec<Bookmark>,
+}
+
+impl Bookmarks {
+    pub fn get(&self, slot: usize) -> Option<&Bookmark> {
+        self.list.iter().find(|bookmark| bookmark.slot == slot)
+    }
+
+    /// Stores `orbit` in `slot`, replacing whatever was there but its name.
+    pub fn set(&mut self, slot: usize, orbit: Orbit) {
+        let name = self
+            .get(slot)
+            .map(|bookmark| bookmark.name.clone())
+            .unwrap_or_else(|| format!("Bookmark {}", slot));
+
+        self.list.retain(|bookmark| bookmark.slot != slot);
+        self.list.push(Bookmark { name, slot, orbit });
+    }
+
+    /// Names the bookmark in `slot`, blank names are ignored.
+    pub fn rename(&mut self, slot: usize, name: &str) {
+        let name = name.trim();
+
+        if name.is_empty() {
+            return;
+        }
+
+        if let Some(bookmark) = self.list.iter_mut().find(|bookmark| bookmark.slot == slot) {
+            bookmark.name = name.to_string();
+        }
+    }
+}
+
+/// The slot of the bookmark being named and the name typed so far.
+#[derive(Resource, Debug, Default)]
+struct Naming {
+    slot: Option<usize>,
+    name: String,
+}
+
+#[derive(Component)]
+struct NamePrompt;
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("./fonts/Roboto-Regular.ttf");
+
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font,
+                font_size: 18.,
+                color: Color::WHITE,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(40.),
+            left: Val::Px(10.),
+            ..default()
+        }),
+        NamePrompt,
+    ));
+}
+
+fn bookmark_keys(
+    actions: Res<Actions>,
+    mut bookmarks: ResMut<Bookmarks>,
+    mut naming: ResMut<Naming>,
+    mut control_data: ResMut<ControlData>,
+) {
+    for slot in 1..=9 {
+        if actions.just_pressed(Action::StoreBookmark(slot)) {
+            bookmarks.set(slot, control_data.target());
+
+            *naming = Naming {
+                slot: Some(slot),
+                name: String::new(),
+            };
+        }
+
+        if actions.just_pressed(Action::RecallBookmark(slot)) {
+            if let Some(bookmark) = bookmarks.get(slot) {
+                control_data.jump_to(bookmark.orbit);
+            }
+        }
+    }
+}
+
+fn type_name(
+    mut characters: EventReader<ReceivedCharacter>,
+    keys: Res<Input<KeyCode>>,
+    mut naming: ResMut<Naming>,
+    mut bookmarks: ResMut<Bookmarks>,
+    mut typing: ResMut<Typing>,
+    mut prompt: Query<&mut Text, With<NamePrompt>>,
+) {
+    // Read every frame so the number that stored the bookmark doesn't end up in its name.
+    let typed: String = characters
+        .iter()
+        .map(|event| event.char)
+        .filter(|char| !char.is_control())
+        .collect();
+
+    if !naming.is_changed() && naming.slot.is_none() {
+        return;
+    }
+
+    if let Some(slot) = naming.slot {
+        if keys.just_pressed(KeyCode::Return) {
+            bookmarks.rename(slot, &naming.name);
+            naming.slot = None;
+        } else if keys.just_pressed(KeyCode::Escape) {
+            naming.slot = None;
+        } else if keys.just_pressed(KeyCode::Back) {
+            naming.name.pop();
+        } else if !typed.is_empty() && !naming.is_changed() {
+            naming.name.push_str(&typed);
+        }
+    }
+
+    if !naming.is_changed() {
+        return;
+    }
+
+    typing.0 = naming.slot.is_some();
+
+    prompt.single_mut().sections[0].value = match naming.slot {
+        Some(slot) => format!("Name bookmark {}: {}|", slot, naming.name),
+        None => String::new(),
+    };
+}
+
+/// Points the camera at the middle of `cells` from far enough to see all of them, keeping
+/// the angles the camera had.
+pub fn frame_cells(
+    control_data: &mut ControlData,
+    grid: &Grid,
+    cells: impl IntoIterator<Item = Position>,
+) {
+    let mut min = Vec3::splat(f32::MAX);
+    let mut max = Vec3::splat(f32::MIN);
+    let half = grid.cell_size / 2.;
+
+    for cell in cells {
+        let center = grid.cell_center(&cell);
+
+        min = min.min(center - half);
+        max = max.max(center + half);
+    }
+
+    if min.x > max.x {
+        return;
+    }
+
+    let center = (min + max) / 2.;
+    let size = max - min;
+
+    // Fits the extent vertically in a 45° perspective view, which also covers the
+    // orthographic views where the radius is the visible height.
+    let radius = size.x.max(size.z) * 1.3;
+
+    let mut orbit = control_data.target();
+
+    orbit.pivot = Vec3::new(center.x, grid.origin.y, center.z);
+    orbit.radius = radius;
+
+    control_data.jump_to(orbit);
+}
+
+fn frame_keys(
+    actions: Res<Actions>,
+    world: Res<World>,
+    selection: Res<Selection>,
+    grid: Res<Grid>,
+    mut control_data: ResMut<ControlData>,
+) {
+    if actions.just_pressed(Action::FrameAll) {
+        if let Some((min, max)) = grid.bounds {
+            frame_cells(&mut control_data, &grid, [min, max]);
+            return;
+        }
+
+        frame_cells(
+            &mut control_data,
+            &grid,
+            world.points().map(|point| point.position),
+        );
+    }
+
+    if actions.just_pressed(Action::FrameSelection) {
+        frame_cells(&mut control_data, &grid, selection.cells.iter().copied());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn orbit() -> Orbit {
+        ControlData::default().target()
+    }
+
+    #[test]
+    fn storing_again_keeps_the_name() {
+        let mut bookmarks = Bookmarks::default();
+
+        bookmarks.set(3, orbit());
+        assert_eq!(bookmarks.get(3).unwrap().name, "Bookmark 3");
+
+        bookmarks.rename(3, "  Harbour ");
+        bookmarks.rename(3, "");
+        bookmarks.set(3, orbit());
+
+        assert_eq!(bookmarks.list.len(), 1);
+        assert_eq!(bookmarks.get(3).unwrap().name, "Harbour");
+    }
+
+    #[test]
+    fn framing_fits_maps_past_the_zoom_limit() {
+        let mut control_data = ControlData::default();
+        let grid = Grid::default();
+        let max_radius = control_data.max_radius;
+        let (first, last) = (Position::new(0, 0), Position::new(99, 49));
+
+        frame_cells(&mut control_data, &grid, [first, last]);
+
+        let width = (last.x - first.x + 1) as f32 * grid.cell_size;
+        let fov = PerspectiveProjection::default().fov;
+        let visible = 2. * control_data.radius * (fov / 2.).tan();
+
+        assert!(width > max_radius);
+        assert_eq!(control_data.max_radius, max_radius);
+        assert!(visible >= width, "{} of {} in view", visible, width);
+    }
+}
@@ -0,0 +1,237 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::{
+    input::{
+        mouse::{MouseMotion, MouseWheel},
+        InputSystem,
+    },
+    prelude::*,
+};
+
+use crate::world::{grid::Grid, terrain::Terrain};
+
+use super::{
+    mouse_projection::{MousePointObject, MouseProjection},
+    movement::{CameraMode, ControlData},
+    walk::CameraView,
+};
+
+/// Building with a gamepad.
+///
+/// The left stick moves a cursor over the grid which the camera follows, the right stick
+/// orbits and the triggers zoom. Buttons go through the input map like everything else.
+///
+/// Whichever of mouse and keyboard or gamepad was used last is the [`InputDevice`], while
+/// it's the gamepad the grid cursor takes the place of the mouse in [`MouseProjection`].
+pub struct GamepadPlugin;
+
+impl Plugin for GamepadPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(GamepadConfig::default());
+        app.insert_resource(InputDevice::default());
+        app.insert_resource(GridCursor::default());
+        app.add_systems(PreUpdate, detect_device.after(InputSystem));
+        app.add_systems(
+            Update,
+            (move_grid_cursor, camera_sticks)
+                .run_if(in_state(CameraView::Orbit))
+                .run_if(resource_equals(InputDevice::Gamepad)),
+        );
+    }
+}
+
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InputDevice {
+    #[default]
+    Mouse,
+    Gamepad,
+}
+
+/// `deadzone` How far a stick has to be pushed before it does anything.
+///
+/// `cursor_speed` Grid cursor speed, in radii per second like the keyboard panning.
+///
+/// `follow_distance` How far, in radii, the cursor gets from the pivot before the camera
+/// follows it.
+///
+/// `zoom_speed` Lines of a mouse wheel per second with a trigger fully pressed.
+#[derive(Resource, Debug)]
+pub struct GamepadConfig {
+    pub deadzone: f32,
+    pub cursor_speed: f32,
+    pub follow_distance: f32,
+    pub orbit_speed: f32,
+    pub zoom_speed: f32,
+}
+
+impl Default for GamepadConfig {
+    fn default() -> Self {
+        Self {
+            deadzone: 0.2,
+            cursor_speed: 0.8,
+            follow_distance: 0.3,
+            orbit_speed: 2.,
+            zoom_speed: 30.,
+        }
+    }
+}
+
+/// The cell the gamepad points at, moved with the left stick.
+///
+/// `flicked` Whether the right stick was already pushed to the side, the orthographic
+/// modes turn once per push.
+#[derive(Resource, Default, Debug)]
+pub struct GridCursor {
+    pub position: Vec3,
+    flicked: bool,
+}
+
+/// A stick as a vector, `0` inside the deadzone.
+fn stick(
+    gamepads: &Gamepads,
+    axes: &Axis<GamepadAxis>,
+    x: GamepadAxisType,
+    y: GamepadAxisType,
+    deadzone: f32,
+) -> Vec2 {
+    for gamepad in gamepads.iter() {
+        let value = Vec2::new(
+            axes.get(GamepadAxis::new(gamepad, x)).unwrap_or(0.),
+            axes.get(GamepadAxis::new(gamepad, y)).unwrap_or(0.),
+        );
+
+        if value.length() > deadzone {
+            return value;
+        }
+    }
+
+    Vec2::ZERO
+}
+
+/// How far a trigger is pulled on any gamepad.
+fn trigger(gamepads: &Gamepads, buttons: &Axis<GamepadButton>, button: GamepadButtonType) -> f32 {
+    gamepads
+        .iter()
+        .filter_map(|gamepad| buttons.get(GamepadButton::new(gamepad, button)))
+        .fold(0., f32::max)
+}
+
+fn detect_device(
+    keys: Res<Input<KeyCode>>,
+    buttons: Res<Input<MouseButton>>,
+    mut motion_evr: EventReader<MouseMotion>,
+    mut scroll_evr: EventReader<MouseWheel>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    config: Res<GamepadConfig>,
+    mouse_projection: Res<MouseProjection>,
+    mut grid_cursor: ResMut<GridCursor>,
+    mut device: ResMut<InputDevice>,
+) {
+    let used_mouse = keys.get_just_pressed().next().is_some()
+        || buttons.get_just_pressed().next().is_some()
+        || motion_evr.iter().count() > 0
+        || scroll_evr.iter().count() > 0;
+
+    let used_gamepad = gamepad_buttons.get_just_pressed().next().is_some()
+        || [
+            (GamepadAxisType::LeftStickX, GamepadAxisType::LeftStickY),
+            (GamepadAxisType::RightStickX, GamepadAxisType::RightStickY),
+        ]
+        .into_iter()
+        .any(|(x, y)| stick(&gamepads, &axes, x, y, config.deadzone) != Vec2::ZERO);
+
+    if used_gamepad && *device != InputDevice::Gamepad {
+        // Carry on from wherever the mouse was pointing.
+        grid_cursor.position = mouse_projection.position;
+        *device = InputDevice::Gamepad;
+    } else if used_mouse && !used_gamepad && *device != InputDevice::Mouse {
+        *device = InputDevice::Mouse;
+    }
+}
+
+fn move_grid_cursor(
+    time: Res<Time>,
+    gamepads: Res<Gamepads>,
+    axes: Res<Axis<GamepadAxis>>,
+    config: Res<GamepadConfig>,
+    grid: Res<Grid>,
+    terrain: Res<Terrain>,
+    mut grid_cursor: ResMut<GridCursor>,
+    mut control_data: ResMut<ControlData>,
+    mut mouse_projection: ResMut<MouseProjection>,
+    mut mouse_point: Query<&mut Transform, With<MousePointObject>>,
+) {
+    let amount = stick(
+        &gamepads,
+        &axes,
+        GamepadAxisType::LeftStickX,
+        GamepadAxisType::LeftStickY,
+        config.deadzone,
+    );
+
+    let (forward, right) = control_data.heading();
+    let speed = config.cursor_speed * control_data.radius * time.delta_seconds();
+
+    grid_cursor.position += (right * amount.x + forward * amount.y) * speed;
+    grid_cursor.position.y = grid.origin.y;
+
+    // Keep the cursor in view by dragging the pivot along once it gets too far.
+    let offset = grid_cursor.position - control_data.pivot;
+    let max = config.follow_distance * control_data.radius;
+    let flat = Vec3::new(offset.x, 0., offset.z);
+
+    if flat.length() > max {
+        control_data.pivot += flat - flat.normalize() * max;
+    }
+
+    let cell = grid.cell_at(grid_cursor.position);
+
+    mouse_projection.normal = cell;
+    mouse_projection.position = grid_cursor.position;
+    mouse_projection.position.y = terrain.height_at(&grid, grid_cursor.position);
+    mouse_projection.surface = terrain.normal_at(&grid, grid_cursor.position);
+    mouse_projection.entity = None;
+    mouse_projection.hit = true;
+
+    mouse_point.single_mut().translation = terrain.cell_center(&grid, &cell);
+}
+
+fn camera_sticks(
+    time: Res<Time>,
+    gamepads: Res<Gamepads>,
+    axes: Res<Axis<GamepadAxis>>,
+    trigger_axes: Res<Axis<GamepadButton>>,
+    config: Res<GamepadConfig>,
+    mode: Res<CameraMode>,
+    mut grid_cursor: ResMut<GridCursor>,
+    mut control_data: ResMut<ControlData>,
+) {
+    let dt = time.delta_seconds();
+
+    let look = stick(
+        &gamepads,
+        &axes,
+        GamepadAxisType::RightStickX,
+        GamepadAxisType::RightStickY,
+        config.deadzone,
+    );
+
+    if mode.is_orthographic() {
+        if look.x.abs() > 0.5 && !grid_cursor.flicked {
+            control_data.polar_angle += FRAC_PI_2 * look.x.signum();
+        }
+
+        grid_cursor.flicked = look.x.abs() > 0.5;
+    } else {
+        control_data.orbit(look * config.orbit_speed * dt);
+    }
+
+    let zoom = trigger(&gamepads, &trigger_axes, GamepadButtonType::RightTrigger2)
+        - trigger(&gamepads, &trigger_axes, GamepadButtonType::LeftTrigger2);
+
+    if zoom != 0. {
+        control_data.zoom(zoom * config.zoom_speed * dt, grid_cursor.position);
+    }
+}
@@ -0,0 +1,427 @@
+use std::{collections::HashSet, fs};
+
+use bevy::{ecs::system::SystemParam, input::InputSystem, prelude::*};
+use serde::{Deserialize, Serialize};
+
+/// Turns keys, mouse and gamepad buttons into [`Action`]s, systems read [`Actions`] instead of the
+/// raw input so everything can be rebound from `./input.json`.
+///
+/// When the file is missing it's written with the default bindings. Actions listed under
+/// `unbound` in it stay without a binding.
+pub struct InputMapPlugin;
+
+impl Plugin for InputMapPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(InputMap::load(InputMap::PATH));
+        app.insert_resource(Actions::default());
+        app.insert_resource(Typing::default());
+        app.add_systems(PreUpdate, update_actions.after(InputSystem));
+    }
+}
+
+/// Everything the editor can be told to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    Place,
+    Remove,
+    /// Held while removing, hides the model under the cursor.
+    RemoveMode,
+    Select,
+    Cancel,
+    RotateNext,
+    RotatePrevious,
+    CyclePaletteNext,
+    CyclePalettePrevious,
+    FloorPalette,
+    BuildingPalette,
+    ZonePalette,
+    NaturePalette,
+    TerrainPalette,
+    Save,
+    Load,
+    Orbit,
+    Pan,
+    MoveForward,
+    MoveBack,
+    MoveLeft,
+    MoveRight,
+    OrbitLeft,
+    OrbitRight,
+    CycleCameraMode,
+    Walk,
+    Run,
+    FrameAll,
+    FrameSelection,
+    StoreBookmark(usize),
+    RecallBookmark(usize),
+    Pause,
+    SpeedUp,
+    SlowDown,
+    Step,
+    ToggleStats,
+    TogglePalette,
+    ToggleStreetLights,
+    ToggleGrid,
+    ExpandMap,
+    ShrinkMap,
+    /// Moves everything on the map a cell towards a side, in the order of
+    /// [`crate::world::point::Position::neighbours`].
+    ShiftMap(usize),
+    CycleWeather,
+    AutomaticWeather,
+}
+
+/// Either side of the keyboard counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Modifier {
+    Shift,
+    Control,
+    Alt,
+    Super,
+}
+
+impl Modifier {
+    pub fn keys(&self) -> [KeyCode; 2] {
+        match self {
+            Self::Shift => [KeyCode::ShiftLeft, KeyCode::ShiftRight],
+            Self::Control => [KeyCode::ControlLeft, KeyCode::ControlRight],
+            Self::Alt => [KeyCode::AltLeft, KeyCode::AltRight],
+            Self::Super => [KeyCode::SuperLeft, KeyCode::SuperRight],
+        }
+    }
+
+    fn held(&self, keys: &Input<KeyCode>) -> bool {
+        keys.any_pressed(self.keys())
+    }
+}
+
+/// `Gamepad` buttons count on any connected gamepad.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Trigger {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButtonType),
+}
+
+impl Trigger {
+    fn pressed(&self, input: &RawInput) -> bool {
+        match self {
+            Self::Key(key) => input.keys.pressed(*key),
+            Self::Mouse(button) => input.buttons.pressed(*button),
+            Self::Gamepad(button) => input.gamepads.iter().any(|gamepad| {
+                input
+                    .gamepad_buttons
+                    .pressed(GamepadButton::new(gamepad, *button))
+            }),
+        }
+    }
+
+    fn just_pressed(&self, input: &RawInput) -> bool {
+        match self {
+            Self::Key(key) => input.keys.just_pressed(*key),
+            Self::Mouse(button) => input.buttons.just_pressed(*button),
+            Self::Gamepad(button) => input.gamepads.iter().any(|gamepad| {
+                input
+                    .gamepad_buttons
+                    .just_pressed(GamepadButton::new(gamepad, *button))
+            }),
+        }
+    }
+}
+
+#[derive(SystemParam)]
+struct RawInput<'w> {
+    keys: Res<'w, Input<KeyCode>>,
+    buttons: Res<'w, Input<MouseButton>>,
+    gamepads: Res<'w, Gamepads>,
+    gamepad_buttons: Res<'w, Input<GamepadButton>>,
+}
+
+/// `trigger` with all of `modifiers` held runs `action`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Binding {
+    pub action: Action,
+    #[serde(default)]
+    pub modifiers: Vec<Modifier>,
+    pub trigger: Trigger,
+}
+
+impl Binding {
+    fn key(action: Action, key: KeyCode) -> Self {
+        Self {
+            action,
+            modifiers: vec![],
+            trigger: Trigger::Key(key),
+        }
+    }
+
+    fn mouse(action: Action, button: MouseButton) -> Self {
+        Self {
+            action,
+            modifiers: vec![],
+            trigger: Trigger::Mouse(button),
+        }
+    }
+
+    fn gamepad(action: Action, button: GamepadButtonType) -> Self {
+        Self {
+            action,
+            modifiers: vec![],
+            trigger: Trigger::Gamepad(button),
+        }
+    }
+
+    fn with(mut self, modifier: Modifier) -> Self {
+        self.modifiers.push(modifier);
+        self
+    }
+
+    fn modifiers_held(&self, keys: &Input<KeyCode>) -> bool {
+        self.modifiers.iter().all(|modifier| modifier.held(keys))
+    }
+
+    /// Whether `other` is the same trigger with more modifiers, so holding those should
+    /// run `other` instead, like `Ctrl` + `S` saving instead of moving back.
+    fn is_shadowed_by(&self, other: &Self) -> bool {
+        self.trigger == other.trigger
+            && other.modifiers.len() > self.modifiers.len()
+            && self.modifiers.iter().all(|m| other.modifiers.contains(m))
+    }
+}
+
+/// `unbound` Actions left without a binding on purpose, they don't get their defaults back
+/// when the map is read.
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+pub struct InputMap {
+    pub bindings: Vec<Binding>,
+    #[serde(default)]
+    pub unbound: Vec<Action>,
+}
+
+impl InputMap {
+    pub const PATH: &'static str = "./input.json";
+
+    /// Reads the bindings from `path`, falling back to the defaults if they can't be read.
+    ///
+    /// Actions the file doesn't mention at all, neither bound nor in `unbound`, get their
+    /// default bindings, so files written before an action existed still work.
+    pub fn load(path: &str) -> Self {
+        let Ok(content) = fs::read_to_string(path) else {
+            let map = Self::default();
+
+            if let Err(err) = fs::write(path, serde_json::to_string_pretty(&map).unwrap()) {
+                warn!("Couldn't write the default input map to {}: {}", path, err);
+            }
+
+            return map;
+        };
+
+        match serde_json::from_str::<Self>(&content) {
+            Ok(mut map) => {
+                map.add_missing_defaults();
+                map
+            }
+            Err(err) => {
+                warn!("Invalid input map in {}, using the defaults: {}", path, err);
+                Self::default()
+            }
+        }
+    }
+
+    fn add_missing_defaults(&mut self) {
+        for binding in Self::default().bindings {
+            let mentioned = self.unbound.contains(&binding.action)
+                || self.bindings.iter().any(|b| b.action == binding.action);
+
+            if !mentioned {
+                self.bindings.push(binding);
+            }
+        }
+    }
+
+    /// Bindings that are active right now, leaving out the ones a chord with more modifiers
+    /// takes over from.
+    fn active<'a>(&'a self, input: &'a RawInput) -> impl Iterator<Item = &'a Binding> {
+        let keys = &input.keys;
+
+        self.bindings.iter().filter(move |binding| {
+            binding.trigger.pressed(input)
+                && binding.modifiers_held(keys)
+                && !self
+                    .bindings
+                    .iter()
+                    .any(|other| binding.is_shadowed_by(other) && other.modifiers_held(keys))
+        })
+    }
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        use Action::*;
+        use Modifier::*;
+
+        let mut bindings = vec![
+            Binding::mouse(Place, MouseButton::Left),
+            Binding::mouse(Remove, MouseButton::Left).with(Shift),
+            Binding::key(RemoveMode, KeyCode::ShiftLeft),
+            Binding::key(RemoveMode, KeyCode::ShiftRight),
+            Binding::mouse(Select, MouseButton::Left).with(Control),
+            Binding::key(Cancel, KeyCode::Escape),
+            Binding::key(RotateNext, KeyCode::R),
+            Binding::key(RotatePrevious, KeyCode::R).with(Alt),
+            Binding::key(CyclePaletteNext, KeyCode::BracketRight),
+            Binding::key(CyclePalettePrevious, KeyCode::BracketLeft),
+            Binding::key(FloorPalette, KeyCode::Key1),
+            Binding::key(BuildingPalette, KeyCode::Key2),
+            Binding::key(ZonePalette, KeyCode::Key3),
+            Binding::key(NaturePalette, KeyCode::Key4),
+            Binding::key(TerrainPalette, KeyCode::Key5),
+            Binding::key(Save, KeyCode::S).with(Control),
+            Binding::key(Load, KeyCode::L).with(Control),
+            Binding::mouse(Orbit, MouseButton::Middle),
+            Binding::mouse(Pan, MouseButton::Middle).with(Alt),
+            Binding::key(MoveForward, KeyCode::W),
+            Binding::key(MoveForward, KeyCode::Up),
+            Binding::key(MoveBack, KeyCode::S),
+            Binding::key(MoveBack, KeyCode::Down),
+            Binding::key(MoveLeft, KeyCode::A),
+            Binding::key(MoveLeft, KeyCode::Left),
+            Binding::key(MoveRight, KeyCode::D),
+            Binding::key(MoveRight, KeyCode::Right),
+            Binding::key(OrbitLeft, KeyCode::Q),
+            Binding::key(OrbitRight, KeyCode::E),
+            Binding::key(CycleCameraMode, KeyCode::V),
+            Binding::key(Walk, KeyCode::F),
+            Binding::key(Run, KeyCode::ShiftLeft),
+            Binding::key(Run, KeyCode::ShiftRight),
+            Binding::key(FrameAll, KeyCode::Home),
+            Binding::key(FrameSelection, KeyCode::Z),
+            Binding::key(Pause, KeyCode::Space),
+            Binding::key(SpeedUp, KeyCode::Equals),
+            Binding::key(SpeedUp, KeyCode::NumpadAdd),
+            Binding::key(SlowDown, KeyCode::Minus),
+            Binding::key(SlowDown, KeyCode::NumpadSubtract),
+            Binding::key(Step, KeyCode::Period),
+            Binding::key(ToggleStats, KeyCode::F1),
+            Binding::key(TogglePalette, KeyCode::F2),
+            Binding::key(ToggleStreetLights, KeyCode::N),
+            Binding::key(ToggleGrid, KeyCode::G),
+            Binding::key(ExpandMap, KeyCode::PageUp),
+            Binding::key(ShrinkMap, KeyCode::PageDown),
+            Binding::key(ShiftMap(0), KeyCode::Up).with(Control),
+            Binding::key(ShiftMap(1), KeyCode::Right).with(Control),
+            Binding::key(ShiftMap(2), KeyCode::Down).with(Control),
+            Binding::key(ShiftMap(3), KeyCode::Left).with(Control),
+            Binding::key(CycleWeather, KeyCode::F3),
+            Binding::key(AutomaticWeather, KeyCode::F3).with(Shift),
+            Binding::gamepad(Place, GamepadButtonType::South),
+            Binding::gamepad(Remove, GamepadButtonType::East),
+            Binding::gamepad(RotateNext, GamepadButtonType::West),
+            Binding::gamepad(CycleCameraMode, GamepadButtonType::North),
+            Binding::gamepad(CyclePalettePrevious, GamepadButtonType::LeftTrigger),
+            Binding::gamepad(CyclePaletteNext, GamepadButtonType::RightTrigger),
+            Binding::gamepad(Pause, GamepadButtonType::Start),
+            Binding::gamepad(ToggleStats, GamepadButtonType::Select),
+        ];
+
+        let slots = [
+            KeyCode::Key1,
+            KeyCode::Key2,
+            KeyCode::Key3,
+            KeyCode::Key4,
+            KeyCode::Key5,
+            KeyCode::Key6,
+            KeyCode::Key7,
+            KeyCode::Key8,
+            KeyCode::Key9,
+        ];
+
+        for (i, key) in slots.into_iter().enumerate() {
+            bindings.push(Binding::key(StoreBookmark(i + 1), key).with(Control));
+            bindings.push(Binding::key(RecallBookmark(i + 1), key).with(Alt));
+        }
+
+        Self {
+            bindings,
+            unbound: vec![],
+        }
+    }
+}
+
+/// Whether a text field has the keyboard, key bindings are left alone while it does.
+#[derive(Resource, Debug, Default)]
+pub struct Typing(pub bool);
+
+/// The actions held down this frame, read like [`Input`].
+#[derive(Resource, Debug, Default)]
+pub struct Actions {
+    pressed: HashSet<Action>,
+    just_pressed: HashSet<Action>,
+    just_released: HashSet<Action>,
+}
+
+impl Actions {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.just_pressed.contains(&action)
+    }
+
+    pub fn just_released(&self, action: Action) -> bool {
+        self.just_released.contains(&action)
+    }
+}
+
+fn update_actions(
+    input: RawInput,
+    input_map: Res<InputMap>,
+    typing: Res<Typing>,
+    mut actions: ResMut<Actions>,
+) {
+    let mut pressed = HashSet::new();
+    let mut just_pressed = HashSet::new();
+
+    for binding in input_map.active(&input) {
+        if typing.0 && matches!(binding.trigger, Trigger::Key(_)) {
+            continue;
+        }
+
+        pressed.insert(binding.action);
+
+        // Only the trigger starts an action, holding it and then pressing the modifiers
+        // doesn't.
+        if binding.trigger.just_pressed(&input) {
+            just_pressed.insert(binding.action);
+        }
+    }
+
+    actions.just_released = actions.pressed.difference(&pressed).copied().collect();
+    actions.just_pressed = just_pressed;
+    actions.pressed = pressed;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_actions_the_file_doesnt_mention_get_defaults() {
+        let mut map: InputMap = serde_json::from_str(
+            r#"{
+                "bindings": [{"action": "Place", "trigger": {"Key": "Space"}}],
+                "unbound": ["ToggleGrid"]
+            }"#,
+        )
+        .unwrap();
+
+        map.add_missing_defaults();
+
+        let bound = |action| map.bindings.iter().filter(|b| b.action == action).count();
+
+        assert_eq!(bound(Action::Place), 1);
+        assert_eq!(bound(Action::ToggleGrid), 0);
+        assert!(bound(Action::FrameAll) > 0);
+    }
+}
@@ -1,18 +1,30 @@
 use bevy::prelude::*;
 
+pub mod bookmarks;
+pub mod gamepad;
+pub mod input_map;
 pub mod model_cursor;
 pub mod mouse_projection;
 pub mod movement;
+pub mod picking;
 pub mod place_model;
+pub mod selection;
+pub mod walk;
 
 pub struct ControlPlugin;
 
 impl Plugin for ControlPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins((
+            input_map::InputMapPlugin,
+            gamepad::GamepadPlugin,
             movement::MovementPlugin,
             mouse_projection::ProjectionPlugin,
+            picking::PickingPlugin,
             place_model::PlacePlugin,
+            walk::WalkPlugin,
+            selection::SelectionPlugin,
+            bookmarks::BookmarksPlugin,
         ));
     }
 }
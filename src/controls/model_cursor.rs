@@ -1,6 +1,10 @@
 use bevy::prelude::Resource;
 
-use crate::models::{BuildingModel, FloorModel, Meta};
+use crate::{
+    models::{BuildingModel, FloorModel, Meta, NatureModel},
+    simulation::zoning::Zone,
+    world::terrain::TerrainTool,
+};
 
 ///
 /// This cursor keeps track of what item is selected to be place down
@@ -8,6 +12,9 @@ use crate::models::{BuildingModel, FloorModel, Meta};
 pub enum ModelCursor {
     Floor(usize),
     Buildings(usize),
+    Nature(usize),
+    Zones(usize),
+    Terrain(usize),
 }
 
 impl Default for ModelCursor {
@@ -30,20 +37,87 @@ impl ModelCursor {
             }
         }
 
+        if let Self::Nature(_) = self {
+            if let Self::Nature(_) = other {
+                return true;
+            }
+        }
+
+        if let Self::Zones(_) = self {
+            if let Self::Zones(_) = other {
+                return true;
+            }
+        }
+
+        if let Self::Terrain(_) = self {
+            if let Self::Terrain(_) = other {
+                return true;
+            }
+        }
+
         false
     }
 
+    /// Every entry of every category, in palette order.
+    pub fn all() -> Vec<Self> {
+        let categories = [
+            Self::Floor(0),
+            Self::Buildings(0),
+            Self::Nature(0),
+            Self::Zones(0),
+            Self::Terrain(0),
+        ];
+
+        categories
+            .into_iter()
+            .flat_map(|category| {
+                (0..category.max()).map(move |index| {
+                    let mut cursor = category.clone();
+                    cursor.set(index);
+                    cursor
+                })
+            })
+            .collect()
+    }
+
+    /// Name of the category in the palette.
+    pub fn category(&self) -> &'static str {
+        match self {
+            Self::Floor(_) => "Floor",
+            Self::Buildings(_) => "Buildings",
+            Self::Nature(_) => "Nature",
+            Self::Zones(_) => "Zones",
+            Self::Terrain(_) => "Terrain",
+        }
+    }
+
+    /// Name of the entry in the palette, zones and terrain tools share a marker model so
+    /// they go by their own names.
+    pub fn name(&self) -> String {
+        match self {
+            Self::Zones(index) => format!("{:?}", Zone::index(*index)),
+            Self::Terrain(index) => format!("{:?}", TerrainTool::index(*index)),
+            _ => self.meta().name(),
+        }
+    }
+
     pub fn max(&self) -> usize {
         match self {
             Self::Floor(_) => FloorModel::len(),
             Self::Buildings(_) => BuildingModel::len(),
+            Self::Nature(_) => NatureModel::len(),
+            Self::Zones(_) => Zone::len(),
+            Self::Terrain(_) => TerrainTool::len(),
         }
     }
 
     pub fn meta(&self) -> Meta {
         match self {
-            Self::Floor(index) => FloorModel::index(index.clone()).get_meta().clone(),
-            Self::Buildings(index) => BuildingModel::index(index.clone()).get_meta().clone(),
+            Self::Floor(index) => FloorModel::index(index.clone()).get_meta(),
+            Self::Buildings(index) => BuildingModel::index(index.clone()).get_meta(),
+            Self::Nature(index) => NatureModel::index(index.clone()).get_meta(),
+            Self::Zones(index) => Zone::index(index.clone()).meta(),
+            Self::Terrain(index) => TerrainTool::index(index.clone()).meta(),
         }
     }
 
@@ -51,6 +125,9 @@ impl ModelCursor {
         match self {
             Self::Floor(i) => i.clone(),
             Self::Buildings(i) => i.clone(),
+            Self::Nature(i) => i.clone(),
+            Self::Zones(i) => i.clone(),
+            Self::Terrain(i) => i.clone(),
         }
     }
 
@@ -58,6 +135,9 @@ impl ModelCursor {
         match self {
             Self::Floor(i) => *i = c,
             Self::Buildings(i) => *i = c,
+            Self::Nature(i) => *i = c,
+            Self::Zones(i) => *i = c,
+            Self::Terrain(i) => *i = c,
         }
     }
 }
@@ -69,6 +149,9 @@ impl std::ops::Add<usize> for ModelCursor {
         match self {
             Self::Buildings(c) => Self::Buildings(c + rhs),
             Self::Floor(c) => Self::Floor(c + rhs),
+            Self::Nature(c) => Self::Nature(c + rhs),
+            Self::Zones(c) => Self::Zones(c + rhs),
+            Self::Terrain(c) => Self::Terrain(c + rhs),
         }
     }
 }
@@ -80,6 +163,9 @@ impl std::ops::Sub<usize> for ModelCursor {
         match self {
             Self::Buildings(c) => Self::Buildings(c - rhs),
             Self::Floor(c) => Self::Floor(c - rhs),
+            Self::Nature(c) => Self::Nature(c - rhs),
+            Self::Zones(c) => Self::Zones(c - rhs),
+            Self::Terrain(c) => Self::Terrain(c - rhs),
         }
     }
 }
@@ -100,6 +186,9 @@ impl std::ops::AddAssign<usize> for ModelCursor {
         match self {
             Self::Buildings(c) => apply_or_reset(c),
             Self::Floor(c) => apply_or_reset(c),
+            Self::Nature(c) => apply_or_reset(c),
+            Self::Zones(c) => apply_or_reset(c),
+            Self::Terrain(c) => apply_or_reset(c),
         };
     }
 }
@@ -120,6 +209,9 @@ impl std::ops::SubAssign<usize> for ModelCursor {
         match self {
             Self::Buildings(val) => apply_or_reset(val),
             Self::Floor(val) => apply_or_reset(val),
+            Self::Nature(val) => apply_or_reset(val),
+            Self::Zones(val) => apply_or_reset(val),
+            Self::Terrain(val) => apply_or_reset(val),
         };
     }
 }
@@ -1,28 +1,43 @@
-use bevy::{math::Vec4Swizzles, prelude::*, window::PrimaryWindow};
+use bevy::{prelude::*, window::PrimaryWindow};
 
-use crate::world::point::Position;
+use crate::world::{grid::Grid, point::Position, terrain::Terrain};
 
-/// This plugin projects the X, Y position from the screen onto the 3d world and
-/// returns the X, Z position on Y intersect. It also provides a snap normalized prosition
-/// which means that it turns anything between `0 - 20` to `1`, and anything between `20 - 40` to `2`, and so on.
+use super::{gamepad::InputDevice, movement::MainCamera, picking::Picker};
+
+/// This plugin projects the X, Y position from the screen onto the 3d world through the
+/// [`Picker`], landing on placed models or the terrain. It also provides the cell that was
+/// hit, the one whose middle is within half a [`Grid`] `cell_size` of the hit.
 pub struct ProjectionPlugin;
 
-/// Information about mouse projection 
-/// 
-/// `normal` The position in chunks of 20, meaning `0 - 20` is `1`, `21 - 40` is `2` etc.
-/// 
-/// `position` The real position of the mouse on the world at the Y intersect. 
+/// Information about mouse projection
+///
+/// `normal` The cell under the mouse, for a placed model the cell it stands on.
+///
+/// `position` The real position of the mouse on the world, where the ray hit the terrain or
+/// a placed model.
+///
+/// `surface` and `entity` The normal of the face under the mouse and the placed model it
+/// belongs to, if it's not the ground.
+///
+/// `hit` Whether the mouse is over anything at all, when it isn't the rest is left as it
+/// was.
 #[derive(Resource, Default, Debug)]
 pub struct MouseProjection {
     pub normal: Position,
     pub position: Vec3,
+    pub surface: Vec3,
+    pub entity: Option<Entity>,
+    pub hit: bool,
 }
 
 impl Plugin for ProjectionPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(MouseProjection::default());
         app.add_systems(Startup, setup);
-        app.add_systems(PostUpdate, mouse_on_y_intersection);
+        app.add_systems(
+            PostUpdate,
+            mouse_on_y_intersection.run_if(resource_equals(InputDevice::Mouse)),
+        );
     }
 }
 
@@ -37,18 +52,20 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
 }
 
 fn mouse_on_y_intersection(
-    camera_q: Query<
-        (&Camera, &GlobalTransform, &Transform),
-        (With<Camera3d>, Without<MousePointObject>),
-    >,
+    camera_q: Query<(&Camera, &GlobalTransform), (With<MainCamera>, Without<MousePointObject>)>,
     window_q: Query<&Window, With<PrimaryWindow>>,
     mut mouse_point: Query<&mut Transform, With<MousePointObject>>,
     mut mouse_projection: ResMut<MouseProjection>,
+    picker: Picker,
+    grid: Res<Grid>,
+    terrain: Res<Terrain>,
 ) {
     let window = window_q.single();
     let mut mouse_point_transform = mouse_point.single_mut();
 
-    let (camera, c_g_transform, c_transform) = camera_q.single();
+    let (camera, c_g_transform) = camera_q.single();
+
+    mouse_projection.hit = false;
 
     let mouse_pos = if let Some(pos) = window.cursor_position() {
         pos
@@ -56,37 +73,24 @@ fn mouse_on_y_intersection(
         return;
     };
 
-    let mouse_ndc = Vec3::new(
-        (2. * mouse_pos.x) / window.width() - 1.,
-        1. - (2. * mouse_pos.y) / window.height(),
-        1.,
-    );
-
-    let ray_clip = mouse_ndc.extend(1.);
-
-    let mut ray_eye = camera.projection_matrix().inverse() * ray_clip;
-
-    ray_eye = Vec4::new(ray_eye.x, ray_eye.y, -1.0, 0.0);
-
-    let ray_direction = (c_g_transform.compute_matrix() * ray_eye).xyz().normalize();
-
-    let t = -c_transform.translation.y / ray_direction.y;
-
-    let pos = c_transform.translation + t * ray_direction;
-
-
-    let snap_normal_area = 20.;
-
-    let real_snap_position = Vec3::new(
-        (pos.x / snap_normal_area).round() * snap_normal_area,
-        0.,
-        (pos.z / snap_normal_area).round() * snap_normal_area,
-    );
+    // Goes through the camera's own projection, so the ray starts on the near plane and
+    // works the same for perspective and orthographic cameras.
+    let ray = if let Some(ray) = camera.viewport_to_world(c_g_transform, mouse_pos) {
+        ray
+    } else {
+        return;
+    };
 
-    let normalized_position = Vec3::new((pos.x / snap_normal_area).round(), 0., (pos.z / snap_normal_area).round());
+    // Pointing at the sky keeps the last cell, but nothing should act on it.
+    let Some(hit) = picker.cast(ray) else {
+        return;
+    };
 
-    mouse_projection.normal =
-        Position::new(normalized_position.x as i32, normalized_position.z as i32);
+    mouse_projection.normal = hit.cell;
+    mouse_projection.position = hit.position;
+    mouse_projection.surface = hit.normal;
+    mouse_projection.entity = hit.entity;
+    mouse_projection.hit = true;
 
-    mouse_point_transform.translation = real_snap_position;
+    mouse_point_transform.translation = terrain.cell_center(&grid, &hit.cell);
 }
@@ -1,103 +1,531 @@
-use std::f32::consts::PI;
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI};
 
 use bevy::{
-    input::mouse::{MouseMotion, MouseWheel},
+    input::mouse::{MouseMotion, MouseScrollUnit, MouseWheel},
     prelude::*,
+    render::camera::ScalingMode,
+    window::PrimaryWindow,
 };
+use serde::{Deserialize, Serialize};
 
-/// Allows for moving the camera around a single pivot point which can also be moved. 
+use super::{
+    gamepad::InputDevice,
+    input_map::{Action, Actions},
+    mouse_projection::MouseProjection,
+    walk::CameraView,
+};
+
+/// The camera the world is seen through, other 3d cameras only render into textures.
+#[derive(Component)]
+pub struct MainCamera;
+
+/// Allows for moving the camera around a single pivot point which can also be moved.
+///
+/// The pivot is moved with `WASD` or the arrow keys, by holding the cursor against the
+/// edges of the window or with `Alt` + middle mouse. `Q` and `E` orbit around it, as does
+/// dragging with middle mouse, and scrolling zooms towards whatever is under the cursor.
+///
+/// Input only moves the target in [`ControlData`], the camera eases towards it every frame.
+///
+/// `V` switches between the [`CameraMode`]s.
 pub struct MovementPlugin;
 
 impl Plugin for MovementPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(ControlData::default());
-        app.add_systems(Update, update_controls);
+        app.insert_resource(CameraMode::default());
+        app.register_type::<ControlData>();
+        app.add_systems(
+            Update,
+            (
+                switch_mode,
+                keyboard_controls,
+                edge_scrolling,
+                update_controls,
+                ease_camera,
+            )
+                .chain()
+                .run_if(in_state(CameraView::Orbit)),
+        );
     }
 }
 
-#[derive(Resource)]
-struct ControlData {
-    pivot: Vec3,
-    radius: f32,
-    polar_angle: f32,
-    azimuthal_angle: f32,
-    move_speed: f32,
+/// `Perspective` The free orbiting camera.
+///
+/// `TopDown` Orthographic, looking straight down.
+///
+/// `Isometric` Orthographic from a fixed angle, only rotates in steps of 90°.
+///
+/// In the orthographic modes the radius sets how much of the world fits in the view.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CameraMode {
+    #[default]
+    Perspective,
+    TopDown,
+    Isometric,
+}
+
+impl CameraMode {
+    /// Angle from the vertical of a true isometric view.
+    pub const ISOMETRIC_ANGLE: f32 = 0.955_316_6;
+
+    /// How far from the pivot the camera sits in the orthographic modes, far enough to not
+    /// clip through tall models.
+    const ORTHOGRAPHIC_DISTANCE: f32 = 600.;
+
+    pub fn next(&self) -> Self {
+        match self {
+            Self::Perspective => Self::TopDown,
+            Self::TopDown => Self::Isometric,
+            Self::Isometric => Self::Perspective,
+        }
+    }
+
+    pub fn is_orthographic(&self) -> bool {
+        !matches!(self, Self::Perspective)
+    }
+
+    /// The polar angle closest to `angle` this mode can look from.
+    fn snap_polar(&self, angle: f32) -> f32 {
+        match self {
+            Self::Perspective => angle,
+            Self::TopDown => (angle / FRAC_PI_2).round() * FRAC_PI_2,
+            Self::Isometric => ((angle - FRAC_PI_4) / FRAC_PI_2).round() * FRAC_PI_2 + FRAC_PI_4,
+        }
+    }
+
+    pub fn projection(&self, radius: f32) -> Projection {
+        match self {
+            Self::Perspective => Projection::Perspective(PerspectiveProjection::default()),
+            _ => Projection::Orthographic(OrthographicProjection {
+                far: Self::ORTHOGRAPHIC_DISTANCE * 3.,
+                scaling_mode: ScalingMode::FixedVertical(radius),
+                ..default()
+            }),
+        }
+    }
+}
+
+/// A position of the camera around a pivot.
+#[derive(Debug, Clone, Copy, PartialEq, Reflect, Serialize, Deserialize)]
+pub struct Orbit {
+    pub pivot: Vec3,
+    pub radius: f32,
+    pub polar_angle: f32,
+    pub azimuthal_angle: f32,
+}
+
+impl Orbit {
+    pub fn translation(&self) -> Vec3 {
+        self.translation_at(self.radius)
+    }
+
+    /// Camera position at `r` from the pivot instead of the radius.
+    fn translation_at(&self, r: f32) -> Vec3 {
+        let pvt = self.pivot;
+        let p_angle = self.polar_angle;
+        let a_angle = self.azimuthal_angle;
+
+        Vec3::new(
+            pvt.x + r * a_angle.sin() * p_angle.cos(),
+            pvt.y + r * a_angle.cos(),
+            pvt.z + r * a_angle.sin() * p_angle.sin(),
+        )
+    }
+
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self {
+            pivot: self.pivot.lerp(other.pivot, t),
+            radius: self.radius + (other.radius - self.radius) * t,
+            polar_angle: self.polar_angle + (other.polar_angle - self.polar_angle) * t,
+            azimuthal_angle: self.azimuthal_angle
+                + (other.azimuthal_angle - self.azimuthal_angle) * t,
+        }
+    }
+}
+
+/// `pivot`, `radius`, `polar_angle` and `azimuthal_angle` The orbit the camera is heading
+/// to, `current` is where it's at.
+///
+/// `key_speed` Keyboard and edge panning speed, in radii per second so that it feels the
+/// same at any zoom.
+///
+/// `edge_margin` Distance in pixels from the window edge that starts edge scrolling, `0`
+/// turns it off.
+///
+/// `damping` How quickly the camera catches up with the target, higher is snappier and `0`
+/// jumps straight to it.
+///
+/// `inertia` Seconds it takes for a drag to come to a stop once the mouse is let go, `0`
+/// stops right away.
+///
+/// `scroll_pixels_per_line` Converts touchpad scrolling, which comes in pixels, into the
+/// lines of a mouse wheel.
+///
+/// `frame_radius` How far out framing and bookmarks may take the camera, past the
+/// `max_radius` zooming stops at, so large maps still fit in view.
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct ControlData {
+    pub pivot: Vec3,
+    pub radius: f32,
+    pub polar_angle: f32,
+    pub azimuthal_angle: f32,
+    pub move_speed: f32,
+    pub key_speed: f32,
+    pub orbit_speed: f32,
+    pub edge_margin: f32,
+    pub zoom_speed: f32,
+    pub min_radius: f32,
+    pub max_radius: f32,
+    pub frame_radius: f32,
+    pub damping: f32,
+    pub inertia: f32,
+    pub scroll_pixels_per_line: f32,
+    pub current: Orbit,
+    #[reflect(ignore)]
+    pan_velocity: Vec3,
+    #[reflect(ignore)]
+    orbit_velocity: Vec2,
 }
 
 impl Default for ControlData {
     fn default() -> Self {
-        Self {
+        let orbit = Orbit {
             pivot: Vec3::ZERO,
             radius: 200.,
             polar_angle: 2.,
             azimuthal_angle: 1.,
+        };
+
+        Self {
+            pivot: orbit.pivot,
+            radius: orbit.radius,
+            polar_angle: orbit.polar_angle,
+            azimuthal_angle: orbit.azimuthal_angle,
             move_speed: 0.5,
+            key_speed: 1.,
+            orbit_speed: 1.5,
+            edge_margin: 8.,
+            zoom_speed: 2.,
+            min_radius: 3.,
+            max_radius: 250.,
+            frame_radius: 5000.,
+            damping: 12.,
+            inertia: 0.15,
+            scroll_pixels_per_line: 20.,
+            current: orbit,
+            pan_velocity: Vec3::ZERO,
+            orbit_velocity: Vec2::ZERO,
+        }
+    }
+}
+
+impl ControlData {
+    pub fn target(&self) -> Orbit {
+        Orbit {
+            pivot: self.pivot,
+            radius: self.radius,
+            polar_angle: self.polar_angle,
+            azimuthal_angle: self.azimuthal_angle,
         }
     }
+
+    pub fn set_target(&mut self, orbit: Orbit) {
+        self.pivot = orbit.pivot;
+        self.radius = orbit.radius.clamp(self.min_radius, self.max_radius);
+        self.polar_angle = orbit.polar_angle;
+        self.azimuthal_angle = orbit.azimuthal_angle;
+        self.pan_velocity = Vec3::ZERO;
+        self.orbit_velocity = Vec2::ZERO;
+    }
+
+    /// Like [`Self::set_target`], but `orbit` can be as far out as `frame_radius`.
+    pub fn jump_to(&mut self, orbit: Orbit) {
+        self.set_target(orbit);
+        self.radius = orbit.radius.clamp(self.min_radius, self.frame_radius);
+    }
+
+    /// Direction the camera faces and the one to its right, both flat on the ground.
+    pub fn heading(&self) -> (Vec3, Vec3) {
+        let forward = Vec3::new(-self.polar_angle.cos(), 0., -self.polar_angle.sin());
+        let right = Vec3::new(-forward.z, 0., forward.x);
+
+        (forward, right)
+    }
+
+    /// Moves the pivot by `amount` along the heading, `x` is to the right and `y` forward.
+    pub fn pan(&mut self, amount: Vec2) {
+        let (forward, right) = self.heading();
+
+        self.pivot += (right * amount.x + forward * amount.y) * self.radius;
+    }
+
+    pub fn orbit(&mut self, delta: Vec2) {
+        self.polar_angle += delta.x;
+        self.azimuthal_angle = (self.azimuthal_angle - delta.y).clamp(0.01, PI - 0.01);
+    }
+
+    /// Zooms in by `lines` of scrolling, pulling the pivot towards `towards` by as much as the
+    /// radius shrank so that point stays under the cursor.
+    pub fn zoom(&mut self, lines: f32, towards: Vec3) {
+        // Zooming in from past `max_radius`, after framing, doesn't snap back to it first.
+        let max = self.max_radius.max(self.radius);
+        let n = (self.radius - self.zoom_speed * lines).clamp(self.min_radius, max);
+
+        let amount = 1. - n / self.radius;
+        let mut offset = (towards - self.pivot) * amount;
+        offset.y = 0.;
+
+        self.pivot += offset;
+        self.radius = n;
+    }
+}
+
+fn switch_mode(
+    actions: Res<Actions>,
+    mut mode: ResMut<CameraMode>,
+    mut control_config: ResMut<ControlData>,
+    mut q: Query<&mut Projection, With<MainCamera>>,
+) {
+    if !actions.just_pressed(Action::CycleCameraMode) {
+        return;
+    }
+
+    *mode = mode.next();
+
+    control_config.polar_angle = mode.snap_polar(control_config.polar_angle);
+    control_config.azimuthal_angle = match *mode {
+        CameraMode::Perspective => ControlData::default().azimuthal_angle,
+        CameraMode::TopDown => 0.01,
+        CameraMode::Isometric => CameraMode::ISOMETRIC_ANGLE,
+    };
+
+    for mut projection in q.iter_mut() {
+        *projection = mode.projection(control_config.current.radius);
+    }
+}
+
+fn keyboard_controls(
+    time: Res<Time>,
+    actions: Res<Actions>,
+    mode: Res<CameraMode>,
+    mut control_config: ResMut<ControlData>,
+) {
+    let mut direction = Vec2::ZERO;
+
+    if actions.pressed(Action::MoveForward) {
+        direction.y += 1.;
+    }
+
+    if actions.pressed(Action::MoveBack) {
+        direction.y -= 1.;
+    }
+
+    if actions.pressed(Action::MoveRight) {
+        direction.x += 1.;
+    }
+
+    if actions.pressed(Action::MoveLeft) {
+        direction.x -= 1.;
+    }
+
+    let dt = time.delta_seconds();
+
+    if direction != Vec2::ZERO {
+        let speed = control_config.key_speed;
+
+        control_config.pan(direction.normalize() * speed * dt);
+    }
+
+    if mode.is_orthographic() {
+        if actions.just_pressed(Action::OrbitLeft) {
+            control_config.polar_angle -= FRAC_PI_2;
+        }
+
+        if actions.just_pressed(Action::OrbitRight) {
+            control_config.polar_angle += FRAC_PI_2;
+        }
+
+        return;
+    }
+
+    if actions.pressed(Action::OrbitLeft) {
+        control_config.polar_angle -= control_config.orbit_speed * dt;
+    }
+
+    if actions.pressed(Action::OrbitRight) {
+        control_config.polar_angle += control_config.orbit_speed * dt;
+    }
+}
+
+fn edge_scrolling(
+    time: Res<Time>,
+    window_q: Query<&Window, With<PrimaryWindow>>,
+    device: Res<InputDevice>,
+    mut control_config: ResMut<ControlData>,
+) {
+    let margin = control_config.edge_margin;
+
+    // A mouse left at the edge shouldn't scroll while playing on a gamepad.
+    if margin <= 0. || *device != InputDevice::Mouse {
+        return;
+    }
+
+    let window = window_q.single();
+
+    if !window.focused {
+        return;
+    }
+
+    let Some(cursor) = window.cursor_position() else {
+        return;
+    };
+
+    let mut direction = Vec2::ZERO;
+
+    if cursor.x <= margin {
+        direction.x -= 1.;
+    } else if cursor.x >= window.width() - margin {
+        direction.x += 1.;
+    }
+
+    if cursor.y <= margin {
+        direction.y += 1.;
+    } else if cursor.y >= window.height() - margin {
+        direction.y -= 1.;
+    }
+
+    if direction != Vec2::ZERO {
+        let speed = control_config.key_speed;
+
+        control_config.pan(direction.normalize() * speed * time.delta_seconds());
+    }
 }
 
 fn update_controls(
-    mut q: Query<&mut Transform, (With<Camera>, With<Camera3d>)>,
+    time: Res<Time>,
     mut scroll_evr: EventReader<MouseWheel>,
     mut control_config: ResMut<ControlData>,
     mut motion_evr: EventReader<MouseMotion>,
-    buttons: Res<Input<MouseButton>>,
-    keys: Res<Input<KeyCode>>
+    actions: Res<Actions>,
+    mouse_projection: Res<MouseProjection>,
+    mode: Res<CameraMode>,
 ) {
-    let mut transform = q.single_mut();
-
-    let pvt = control_config.pivot;
-    let r = control_config.radius;
-    let p_angle = control_config.polar_angle;
-    let a_angle = control_config.azimuthal_angle;
+    let (forward, right) = control_config.heading();
 
-    let new_pos = Vec3::new(
-        pvt.x + r * a_angle.sin() * p_angle.cos(),
-        pvt.y + r * a_angle.cos(),
-        pvt.z + r * a_angle.sin() * p_angle.sin(),
-    );
+    let dragging = actions.pressed(Action::Orbit) || actions.pressed(Action::Pan);
 
-    transform.translation = new_pos;
-    transform.look_at(pvt, Vec3::Y);
+    // There's no orbiting in the orthographic modes, so dragging always pans there.
+    let panning = actions.pressed(Action::Pan) || mode.is_orthographic();
 
     let speed = control_config.move_speed;
 
     for ev in scroll_evr.iter() {
-        match ev.unit {
-            bevy::input::mouse::MouseScrollUnit::Line => {
-                let mut n = control_config.radius + 2.0 * -ev.y;
-
-                if n >= 250. {
-                    n = 250.;
-                }
-                if n <= 3. {
-                    n = 3.;
-                }
-
-                control_config.radius = n;
-            }
-            _ => {}
-        }
+        let lines = match ev.unit {
+            MouseScrollUnit::Line => ev.y,
+            MouseScrollUnit::Pixel => ev.y / control_config.scroll_pixels_per_line,
+        };
+
+        // Over the sky there's nothing to zoom towards but the pivot.
+        let towards = if mouse_projection.hit {
+            mouse_projection.position
+        } else {
+            control_config.pivot
+        };
+
+        control_config.zoom(lines, towards);
     }
-    for ev in motion_evr.iter() {
-        if buttons.pressed(MouseButton::Middle) && !keys.pressed(KeyCode::AltLeft) {
-            control_config.polar_angle += ev.delta.x * 0.02 * speed;
-            let mut d_azi_a = control_config.azimuthal_angle - ev.delta.y * 0.02 * speed;
 
-            if d_azi_a >= PI {
-                d_azi_a = PI - 0.01
-            } else if d_azi_a < 0. {
-                d_azi_a = 0.01
-            }
+    let mut orbit = Vec2::ZERO;
+    let mut pan = Vec3::ZERO;
 
-            control_config.azimuthal_angle = d_azi_a;
+    for ev in motion_evr.iter() {
+        if dragging && !panning {
+            orbit += ev.delta * 0.02 * speed;
         }
 
-        if buttons.pressed(MouseButton::Middle) && keys.pressed(KeyCode::AltLeft) {
-            let mut m = transform.rotation * (Vec3::new(ev.delta.x, 0., ev.delta.y) * -speed * 0.3);
-            m.y = 0.;
+        if dragging && panning {
+            pan += (right * -ev.delta.x + forward * ev.delta.y) * speed * 0.3;
+        }
+    }
+
+    if !dragging {
+        return;
+    }
+
+    control_config.orbit(orbit);
+    control_config.pivot += pan;
+
+    // Remember how fast the drag was going so it can carry on after letting go.
+    let dt = time.delta_seconds();
+
+    if dt > 0. {
+        control_config.orbit_velocity = orbit / dt;
+        control_config.pan_velocity = pan / dt;
+    }
+}
+
+fn ease_camera(
+    mut q: Query<(&mut Transform, &mut Projection), (With<Camera>, With<MainCamera>)>,
+    time: Res<Time>,
+    actions: Res<Actions>,
+    mode: Res<CameraMode>,
+    mut control_config: ResMut<ControlData>,
+) {
+    let (mut transform, mut projection) = q.single_mut();
+    let dt = time.delta_seconds();
+
+    if !actions.pressed(Action::Orbit) && !actions.pressed(Action::Pan) {
+        let orbit_velocity = control_config.orbit_velocity;
+        let pan_velocity = control_config.pan_velocity;
+
+        control_config.orbit(orbit_velocity * dt);
+        control_config.pivot += pan_velocity * dt;
+
+        let decay = if control_config.inertia > 0. {
+            (-dt / control_config.inertia).exp()
+        } else {
+            0.
+        };
+
+        control_config.orbit_velocity *= decay;
+        control_config.pan_velocity *= decay;
+    }
+
+    let target = control_config.target();
 
-            control_config.pivot += m;
+    control_config.current = if control_config.damping > 0. {
+        let t = 1. - (-control_config.damping * dt).exp();
+
+        control_config.current.lerp(&target, t)
+    } else {
+        target
+    };
+
+    let current = control_config.current;
+
+    match projection.as_mut() {
+        Projection::Orthographic(ortho) => {
+            ortho.scaling_mode = ScalingMode::FixedVertical(current.radius);
+        }
+        // Framed far out the map would end up behind the far plane.
+        Projection::Perspective(perspective) => {
+            perspective.far = (current.radius * 3.).max(PerspectiveProjection::default().far);
         }
-    }    
+    }
+
+    // Looking straight down, up can't be the world's up, so the heading is used instead.
+    let up = match *mode {
+        CameraMode::TopDown => {
+            Vec3::new(-current.polar_angle.cos(), 0., -current.polar_angle.sin())
+        }
+        _ => Vec3::Y,
+    };
+
+    transform.translation = match *mode {
+        CameraMode::Perspective => current.translation(),
+        _ => current.translation_at(CameraMode::ORTHOGRAPHIC_DISTANCE),
+    };
+    transform.look_at(current.pivot, up);
 }
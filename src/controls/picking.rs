@@ -0,0 +1,159 @@
+use bevy::{ecs::system::SystemParam, math::Ray, prelude::*, render::primitives::Aabb};
+
+use crate::world::{
+    grid::Grid,
+    point::{Point, Position},
+    terrain::Terrain,
+};
+
+/// Marks the meshes of placed models so [`Picker`] can cast against them.
+pub struct PickingPlugin;
+
+impl Plugin for PickingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PreUpdate, mark_pickable);
+    }
+}
+
+/// A mesh that's part of the model of a placed [`Point`], holding the point's entity.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Pickable(pub Entity);
+
+/// Only models that are part of the map, not the preview under the cursor, the weather
+/// particles or the batches, impostors and water drawn for whole chunks.
+fn mark_pickable(
+    mut commands: Commands,
+    meshes: Query<Entity, Added<Handle<Mesh>>>,
+    parents: Query<&Parent>,
+    points: Query<(), With<Point>>,
+) {
+    for entity in meshes.iter() {
+        let Some(point) = parents
+            .iter_ancestors(entity)
+            .find(|ancestor| points.contains(*ancestor))
+        else {
+            continue;
+        };
+
+        commands.entity(entity).insert(Pickable(point));
+    }
+}
+
+/// What a ray ran into.
+///
+/// `cell` The cell that was hit, for a model that's the cell it was placed on rather than
+/// wherever on it the ray landed.
+///
+/// `entity` The placed [`Point`] that was hit, `None` for the bare ground.
+///
+/// `normal` Direction the face that was hit points to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PickHit {
+    pub cell: Position,
+    pub position: Vec3,
+    pub normal: Vec3,
+    pub entity: Option<Entity>,
+    distance: f32,
+}
+
+/// Casts rays against the terrain and the bounding boxes of placed models.
+#[derive(SystemParam)]
+pub struct Picker<'w, 's> {
+    meshes: Query<'w, 's, (&'static Pickable, &'static Aabb, &'static GlobalTransform)>,
+    points: Query<'w, 's, &'static Point>,
+    grid: Res<'w, Grid>,
+    terrain: Res<'w, Terrain>,
+}
+
+impl Picker<'_, '_> {
+    /// The closest hit along `ray`, `None` when it doesn't hit anything, like when it runs
+    /// parallel to flat ground.
+    pub fn cast(&self, ray: Ray) -> Option<PickHit> {
+        let mut closest = ground_hit(ray, &self.grid, &self.terrain);
+
+        for (pickable, aabb, transform) in self.meshes.iter() {
+            let Ok(point) = self.points.get(pickable.0) else {
+                continue;
+            };
+
+            let Some((distance, normal)) = box_hit(ray, aabb, transform) else {
+                continue;
+            };
+
+            if closest.is_some_and(|hit| hit.distance <= distance) {
+                continue;
+            }
+
+            closest = Some(PickHit {
+                cell: point.position,
+                position: ray.origin + ray.direction * distance,
+                normal,
+                entity: Some(pickable.0),
+                distance,
+            });
+        }
+
+        closest
+    }
+}
+
+/// Where `ray` meets the surface of the terrain.
+fn ground_hit(ray: Ray, grid: &Grid, terrain: &Terrain) -> Option<PickHit> {
+    let (distance, normal) = terrain.cast(grid, ray)?;
+    let position = ray.origin + ray.direction * distance;
+
+    Some(PickHit {
+        cell: grid.cell_at(position),
+        position,
+        normal,
+        entity: None,
+        distance,
+    })
+}
+
+/// Slab test in the box's own space, returns the distance along `ray` and the normal of the
+/// face it enters through.
+fn box_hit(ray: Ray, aabb: &Aabb, transform: &GlobalTransform) -> Option<(f32, Vec3)> {
+    let inverse = transform.compute_matrix().inverse();
+    let origin = inverse.transform_point3(ray.origin);
+    let direction = inverse.transform_vector3(ray.direction);
+
+    let min = Vec3::from(aabb.min());
+    let max = Vec3::from(aabb.max());
+
+    let mut near = f32::MIN;
+    let mut far = f32::MAX;
+    let mut normal = Vec3::ZERO;
+
+    for axis in 0..3 {
+        let (o, d) = (origin[axis], direction[axis]);
+
+        if d.abs() < f32::EPSILON {
+            if o < min[axis] || o > max[axis] {
+                return None;
+            }
+
+            continue;
+        }
+
+        let (t1, t2) = ((min[axis] - o) / d, (max[axis] - o) / d);
+        let (t1, t2) = (t1.min(t2), t1.max(t2));
+
+        if t1 > near {
+            near = t1;
+            normal = Vec3::ZERO;
+            normal[axis] = -d.signum();
+        }
+
+        far = far.min(t2);
+    }
+
+    // Missed, behind the ray or the ray starts inside the box.
+    if near > far || near < 0. {
+        return None;
+    }
+
+    let (_, rotation, _) = transform.to_scale_rotation_translation();
+
+    Some((near, rotation * normal))
+}
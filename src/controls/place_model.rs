@@ -1,9 +1,26 @@
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
 
-use crate::{controls::mouse_projection::MousePointObject, world::point::Point};
-
-use super::{model_cursor::ModelCursor, mouse_projection::MouseProjection};
+use crate::{
+    controls::mouse_projection::MousePointObject,
+    hud::PointerOverUi,
+    models::{ModelCache, ModelsState},
+    simulation::economy::Budget,
+    world::{
+        batching::TileRendering,
+        grid::Grid,
+        point::{Point, PointType},
+        spawn_point,
+        water::Water,
+    },
+};
+
+use super::{
+    input_map::{Action, Actions},
+    model_cursor::ModelCursor,
+    mouse_projection::MouseProjection,
+    walk::CameraView,
+};
 
 #[derive(Debug, Clone, Resource, Serialize, Deserialize)]
 pub enum Orientation {
@@ -28,6 +45,15 @@ impl Orientation {
         }
     }
 
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::North(_) => "North",
+            Self::East(_) => "East",
+            Self::South(_) => "South",
+            Self::West(_) => "West",
+        }
+    }
+
     pub fn get_index(&self) -> usize {
         match self {
             Self::North(_) => 0,
@@ -104,13 +130,17 @@ impl Plugin for PlacePlugin {
                 place_model,
                 remove_model,
                 invisible_cursor,
-            ),
+            )
+                .run_if(in_state(CameraView::Orbit))
+                .run_if(in_state(ModelsState::Ready)),
         );
     }
 }
 
+/// `[` and `]` cycle through the models of the current category, `R` rotates and
+/// `Alt` + `R` rotates back. `WASD` and the arrow keys are left for moving the camera.
 fn control_cursor(
-    keys: Res<Input<KeyCode>>,
+    actions: Res<Actions>,
     mut model_cursor: ResMut<ModelCursor>,
     mut orientation: ResMut<Orientation>,
     mut place_delta: ResMut<PlaceDelta>,
@@ -119,49 +149,59 @@ fn control_cursor(
         *place_delta = PlaceDelta::Update;
     };
 
-    if (keys.just_pressed(KeyCode::Right) || keys.just_pressed(KeyCode::D))
-        && keys.pressed(KeyCode::AltLeft)
-    {
+    if actions.just_pressed(Action::RotateNext) {
         report_change();
 
         orientation.next();
     }
 
-    if (keys.just_pressed(KeyCode::Left) || keys.just_pressed(KeyCode::A))
-        && keys.pressed(KeyCode::AltLeft)
-    {
+    if actions.just_pressed(Action::RotatePrevious) {
         report_change();
 
         orientation.previous();
     }
 
-    if (keys.just_pressed(KeyCode::Right) || keys.just_pressed(KeyCode::D))
-        && !keys.pressed(KeyCode::AltLeft)
-    {
+    if actions.just_pressed(Action::CyclePaletteNext) {
         report_change();
 
         *model_cursor += 1;
     }
 
-    if (keys.just_pressed(KeyCode::Left) || keys.just_pressed(KeyCode::A))
-        && !keys.pressed(KeyCode::AltLeft)
-    {
+    if actions.just_pressed(Action::CyclePalettePrevious) {
         report_change();
 
         *model_cursor -= 1;
     }
 
-    if keys.just_pressed(KeyCode::Key1) {
+    if actions.just_pressed(Action::FloorPalette) {
         report_change();
 
         *model_cursor = ModelCursor::Floor(0)
     }
 
-    if keys.just_pressed(KeyCode::Key2) {
+    if actions.just_pressed(Action::BuildingPalette) {
         report_change();
 
         *model_cursor = ModelCursor::Buildings(0)
     }
+
+    if actions.just_pressed(Action::ZonePalette) {
+        report_change();
+
+        *model_cursor = ModelCursor::Zones(0)
+    }
+
+    if actions.just_pressed(Action::NaturePalette) {
+        report_change();
+
+        *model_cursor = ModelCursor::Nature(0)
+    }
+
+    if actions.just_pressed(Action::TerrainPalette) {
+        report_change();
+
+        *model_cursor = ModelCursor::Terrain(0)
+    }
 }
 
 fn update_mouse_point_object(
@@ -169,7 +209,7 @@ fn update_mouse_point_object(
     mut place_delta: ResMut<PlaceDelta>,
     model_cursor: Res<ModelCursor>,
     orientation: ResMut<Orientation>,
-    asset_server: Res<AssetServer>,
+    cache: Res<ModelCache>,
 ) {
     let (mut tf, mut scene) = mouse_point.single_mut();
 
@@ -177,7 +217,7 @@ fn update_mouse_point_object(
         return;
     }
 
-    *scene = asset_server.load(model_cursor.meta().path);
+    *scene = cache.scene(model_cursor.meta().path);
     tf.rotation = Quat::from_rotation_y(orientation.rotation());
 
     *place_delta = PlaceDelta::None;
@@ -187,45 +227,79 @@ fn place_model(
     mut commands: Commands,
     cursor: Res<ModelCursor>,
     mouse_projection: Res<MouseProjection>,
-    buttons: Res<Input<MouseButton>>,
-    asset_server: Res<AssetServer>,
+    actions: Res<Actions>,
+    cache: Res<ModelCache>,
     orientation: Res<Orientation>,
-    keys: Res<Input<KeyCode>>,
+    mut budget: ResMut<Budget>,
+    pointer_over_ui: Res<PointerOverUi>,
+    grid: Res<Grid>,
+    rendering: Res<TileRendering>,
+    water: Res<Water>,
 ) {
-    let model: Handle<Scene> = asset_server.load(cursor.meta().path);
+    if cursor.is(ModelCursor::Zones(0))
+        || cursor.is(ModelCursor::Terrain(0))
+        || pointer_over_ui.0
+        || !mouse_projection.hit
+    {
+        return;
+    }
 
-    if buttons.just_pressed(MouseButton::Left) && !keys.pressed(KeyCode::ShiftLeft) {
-        let mut tf = Transform::from_xyz(
-            mouse_projection.normal.x as f32 * 20.,
-            0.,
-            mouse_projection.normal.y as f32 * 20.,
-        );
+    if actions.just_pressed(Action::Place) {
+        if !grid.contains(&mouse_projection.normal) {
+            warn!("Can't build outside the map");
+            return;
+        }
 
-        tf.rotation = Quat::from_rotation_y(orientation.rotation());
+        let mut has = PointType::from(cursor);
 
-        commands.spawn((
-            SceneBundle {
-                scene: model,
-                transform: tf,
-                ..default()
-            },
+        // Roads carry on over water as bridges, nothing else goes in it.
+        if water.contains(&mouse_projection.normal) {
+            if !has.is_road() {
+                warn!("Can't build on water");
+                return;
+            }
+
+            has = PointType::Bridge(Box::new(has));
+        }
+
+        let meta = has.meta();
+
+        if !budget.spend(meta.cost) {
+            warn!("Not enough funds, {} costs {}", meta.path, meta.cost);
+            return;
+        }
+
+        spawn_point(
+            &mut commands,
+            &cache,
+            &grid,
+            &rendering,
             Point {
-                has: cursor.into(),
+                has,
                 position: mouse_projection.normal,
-                orientation: orientation.clone()
+                orientation: orientation.clone(),
             },
-        ));
+        );
     }
 }
 
 fn remove_model(
     mut commands: Commands,
+    cursor: Res<ModelCursor>,
     mouse_projection: Res<MouseProjection>,
-    buttons: Res<Input<MouseButton>>,
-    keys: Res<Input<KeyCode>>,
+    actions: Res<Actions>,
     points: Query<(Entity, &Point)>,
+    pointer_over_ui: Res<PointerOverUi>,
 ) {
-    if buttons.just_pressed(MouseButton::Left) && keys.pressed(KeyCode::ShiftLeft) {
+    if cursor.is(ModelCursor::Zones(0))
+        || cursor.is(ModelCursor::Terrain(0))
+        || pointer_over_ui.0
+        || !mouse_projection.hit
+    {
+        return;
+    }
+
+    if actions.just_pressed(Action::Remove) {
         for (entity, point) in points.iter() {
             if point.position == mouse_projection.normal {
                 commands.entity(entity).despawn_recursive();
@@ -236,15 +310,15 @@ fn remove_model(
 
 fn invisible_cursor(
     mut mouse_point: Query<&mut Visibility, With<MousePointObject>>,
-    keys: Res<Input<KeyCode>>,
+    actions: Res<Actions>,
 ) {
     let mut visibility = mouse_point.single_mut();
 
-    if keys.just_pressed(KeyCode::ShiftLeft) {
+    if actions.just_pressed(Action::RemoveMode) {
         *visibility = Visibility::Hidden;
     }
 
-    if keys.just_released(KeyCode::ShiftLeft) {
+    if actions.just_released(Action::RemoveMode) {
         *visibility = Visibility::Visible;
     }
 }
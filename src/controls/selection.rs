@@ -0,0 +1,74 @@
+use std::{collections::HashSet, f32::consts::FRAC_PI_2};
+
+use bevy::prelude::*;
+
+use crate::{
+    hud::PointerOverUi,
+    world::{grid::Grid, point::Position, terrain::Terrain},
+};
+
+use super::{
+    input_map::{Action, Actions},
+    mouse_projection::MouseProjection,
+    walk::CameraView,
+};
+
+/// Cells picked with `Ctrl` + click, `Escape` clears them.
+pub struct SelectionPlugin;
+
+impl Plugin for SelectionPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Selection::default());
+        app.add_systems(
+            Update,
+            (
+                select_cell.run_if(in_state(CameraView::Orbit)),
+                draw_selection,
+            ),
+        );
+    }
+}
+
+#[derive(Resource, Default, Debug)]
+pub struct Selection {
+    pub cells: HashSet<Position>,
+}
+
+fn select_cell(
+    actions: Res<Actions>,
+    mouse_projection: Res<MouseProjection>,
+    pointer_over_ui: Res<PointerOverUi>,
+    mut selection: ResMut<Selection>,
+) {
+    if actions.just_pressed(Action::Cancel) {
+        selection.cells.clear();
+    }
+
+    if !actions.just_pressed(Action::Select) || pointer_over_ui.0 || !mouse_projection.hit {
+        return;
+    }
+
+    let cell = mouse_projection.normal;
+
+    if !selection.cells.remove(&cell) {
+        selection.cells.insert(cell);
+    }
+}
+
+fn draw_selection(
+    selection: Res<Selection>,
+    grid: Res<Grid>,
+    terrain: Res<Terrain>,
+    mut gizmos: Gizmos,
+) {
+    let rotation = Quat::from_rotation_x(FRAC_PI_2);
+
+    for pos in selection.cells.iter() {
+        gizmos.rect(
+            terrain.cell_center(&grid, pos) + Vec3::Y * 0.2,
+            rotation,
+            Vec2::splat(grid.cell_size),
+            Color::WHITE,
+        );
+    }
+}
@@ -0,0 +1,245 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::{
+    input::mouse::MouseMotion,
+    prelude::*,
+    window::{CursorGrabMode, PrimaryWindow},
+};
+
+use crate::world::{grid::Grid, terrain::Terrain, water::Water, World};
+
+use super::{
+    input_map::{Action, Actions},
+    mouse_projection::MouseProjection,
+    movement::{CameraMode, ControlData, MainCamera, Orbit},
+};
+
+/// Street level view, `F` drops the camera on the cell under the cursor where it can be
+/// walked around with `WASD` and the mouse, `Escape` goes back to the orbit camera where it
+/// was left.
+pub struct WalkPlugin;
+
+impl Plugin for WalkPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_state::<CameraView>();
+        app.insert_resource(WalkData::default());
+
+        app.add_systems(Update, start_walking.run_if(in_state(CameraView::Orbit)));
+        app.add_systems(
+            Update,
+            (look_around, walk, stop_walking)
+                .chain()
+                .run_if(in_state(CameraView::Walk)),
+        );
+        app.add_systems(OnEnter(CameraView::Walk), grab_cursor);
+        app.add_systems(OnExit(CameraView::Walk), (release_cursor, restore_orbit));
+    }
+}
+
+/// Which camera is in control.
+#[derive(States, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CameraView {
+    #[default]
+    Orbit,
+    Walk,
+}
+
+/// `eye_height` How high above the ground the camera is while walking.
+///
+/// `footprint` Size of the square around the center of a building cell that can't be
+/// walked into, as a fraction of the cell.
+#[derive(Resource, Debug)]
+pub struct WalkData {
+    pub eye_height: f32,
+    pub speed: f32,
+    pub run_multiplier: f32,
+    pub sensitivity: f32,
+    pub footprint: f32,
+    pub position: Vec3,
+    pub yaw: f32,
+    pub pitch: f32,
+    saved: Option<(Orbit, Orbit)>,
+}
+
+impl Default for WalkData {
+    fn default() -> Self {
+        Self {
+            eye_height: 2.,
+            speed: 12.,
+            run_multiplier: 2.5,
+            sensitivity: 0.003,
+            footprint: 0.9,
+            position: Vec3::ZERO,
+            yaw: 0.,
+            pitch: 0.,
+            saved: None,
+        }
+    }
+}
+
+impl WalkData {
+    fn rotation(&self) -> Quat {
+        Quat::from_euler(EulerRot::YXZ, self.yaw, self.pitch, 0.)
+    }
+
+    /// Whether `pos` is inside the footprint of a building or in water away from a bridge.
+    fn is_blocked(&self, world: &World, grid: &Grid, water: &Water, pos: Vec3) -> bool {
+        let cell = grid.cell_at(pos);
+
+        if water.contains(&cell) {
+            return !world.points_at(&cell).any(|point| point.has.is_road());
+        }
+
+        if !world.has_building(&cell) {
+            return false;
+        }
+
+        let half = self.footprint * grid.cell_size / 2.;
+        let center = grid.cell_center(&cell);
+
+        (pos.x - center.x).abs() < half && (pos.z - center.z).abs() < half
+    }
+}
+
+fn start_walking(
+    actions: Res<Actions>,
+    mouse_projection: Res<MouseProjection>,
+    control_data: Res<ControlData>,
+    grid: Res<Grid>,
+    terrain: Res<Terrain>,
+    mut walk_data: ResMut<WalkData>,
+    mut next_view: ResMut<NextState<CameraView>>,
+    mut cameras: Query<&mut Projection, With<MainCamera>>,
+) {
+    if !actions.just_pressed(Action::Walk) || !mouse_projection.hit {
+        return;
+    }
+
+    let cell = mouse_projection.normal;
+
+    walk_data.saved = Some((control_data.target(), control_data.current));
+    walk_data.position = terrain.cell_center(&grid, &cell) + Vec3::Y * walk_data.eye_height;
+
+    // Face the same way the orbit camera was facing.
+    let polar = control_data.current.polar_angle;
+    walk_data.yaw = polar.cos().atan2(polar.sin());
+    walk_data.pitch = 0.;
+
+    for mut projection in cameras.iter_mut() {
+        *projection = Projection::Perspective(PerspectiveProjection::default());
+    }
+
+    next_view.set(CameraView::Walk);
+}
+
+fn look_around(mut motion_evr: EventReader<MouseMotion>, mut walk_data: ResMut<WalkData>) {
+    for ev in motion_evr.iter() {
+        let sensitivity = walk_data.sensitivity;
+
+        walk_data.yaw -= ev.delta.x * sensitivity;
+        walk_data.pitch =
+            (walk_data.pitch - ev.delta.y * sensitivity).clamp(-FRAC_PI_2 + 0.01, FRAC_PI_2 - 0.01);
+    }
+}
+
+fn walk(
+    time: Res<Time>,
+    actions: Res<Actions>,
+    world: Res<World>,
+    grid: Res<Grid>,
+    terrain: Res<Terrain>,
+    water: Res<Water>,
+    mut walk_data: ResMut<WalkData>,
+    mut q: Query<&mut Transform, With<MainCamera>>,
+) {
+    let forward = Vec3::new(-walk_data.yaw.sin(), 0., -walk_data.yaw.cos());
+    let right = Vec3::new(walk_data.yaw.cos(), 0., -walk_data.yaw.sin());
+
+    let mut direction = Vec3::ZERO;
+
+    if actions.pressed(Action::MoveForward) {
+        direction += forward;
+    }
+
+    if actions.pressed(Action::MoveBack) {
+        direction -= forward;
+    }
+
+    if actions.pressed(Action::MoveRight) {
+        direction += right;
+    }
+
+    if actions.pressed(Action::MoveLeft) {
+        direction -= right;
+    }
+
+    let mut speed = walk_data.speed;
+
+    if actions.pressed(Action::Run) {
+        speed *= walk_data.run_multiplier;
+    }
+
+    let step = direction.normalize_or_zero() * speed * time.delta_seconds();
+    let mut position = walk_data.position;
+
+    // Each axis on its own, so walking into a wall at an angle slides along it. Starting
+    // inside a building never blocks, otherwise there'd be no way out.
+    for axis_step in [Vec3::new(step.x, 0., 0.), Vec3::new(0., 0., step.z)] {
+        let next = position + axis_step;
+
+        // Ramps are walked up smoothly, anything steeper is a cliff.
+        let climb = terrain.height_at(&grid, next) - terrain.height_at(&grid, position);
+        let cliff = climb.abs() > grid.level_height / 2.;
+
+        if !cliff
+            && (walk_data.is_blocked(&world, &grid, &water, position)
+                || !walk_data.is_blocked(&world, &grid, &water, next))
+        {
+            position = next;
+        }
+    }
+
+    position.y = terrain.height_at(&grid, position) + walk_data.eye_height;
+    walk_data.position = position;
+
+    let mut tf = q.single_mut();
+
+    tf.translation = walk_data.position;
+    tf.rotation = walk_data.rotation();
+}
+
+fn stop_walking(actions: Res<Actions>, mut next_view: ResMut<NextState<CameraView>>) {
+    if actions.just_pressed(Action::Cancel) {
+        next_view.set(CameraView::Orbit);
+    }
+}
+
+fn grab_cursor(mut window_q: Query<&mut Window, With<PrimaryWindow>>) {
+    let mut window = window_q.single_mut();
+
+    window.cursor.grab_mode = CursorGrabMode::Locked;
+    window.cursor.visible = false;
+}
+
+fn release_cursor(mut window_q: Query<&mut Window, With<PrimaryWindow>>) {
+    let mut window = window_q.single_mut();
+
+    window.cursor.grab_mode = CursorGrabMode::None;
+    window.cursor.visible = true;
+}
+
+fn restore_orbit(
+    mode: Res<CameraMode>,
+    mut control_data: ResMut<ControlData>,
+    mut walk_data: ResMut<WalkData>,
+    mut cameras: Query<&mut Projection, With<MainCamera>>,
+) {
+    if let Some((target, current)) = walk_data.saved.take() {
+        control_data.set_target(target);
+        control_data.current = current;
+    }
+
+    for mut projection in cameras.iter_mut() {
+        *projection = mode.projection(control_data.current.radius);
+    }
+}
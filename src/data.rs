@@ -1,65 +1,302 @@
-use std::fs;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
 
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
-use crate::world::point::Point;
+use crate::{
+    controls::{
+        bookmarks::{Bookmark, Bookmarks},
+        input_map::{Action, Actions},
+    },
+    simulation::{
+        clock::SimClock,
+        economy::Budget,
+        zoning::{ZonedCell, Zones},
+    },
+    world::{
+        chunks::LoadedChunks,
+        grid::Grid,
+        point::{Point, Position},
+        terrain::{Terrain, TerrainCell},
+        water::Water,
+        World,
+    },
+};
 
 pub struct DataPlugin;
 
 impl Plugin for DataPlugin {
     fn build(&self, app: &mut App) {
+        app.insert_resource(SaveState::default());
         app.add_systems(Update, (save_key, load_key));
     }
 }
 
-fn save_key(keys: Res<Input<KeyCode>>, points: Query<&Point>) {
-    let mut points_vecs = vec![];
+/// Points are saved in a file per chunk in here, named after the chunk like `3_-2.json`.
+const CHUNKS_PATH: &str = "./chunks";
 
-    for point in points.iter() {
-        points_vecs.push(point);
+/// A full save is written in here first and only swapped in for [`CHUNKS_PATH`] once every
+/// chunk made it, the files it replaces are kept in `BACKUP_PATH` until then.
+const STAGING_PATH: &str = "./chunks.new";
+const BACKUP_PATH: &str = "./chunks.old";
+
+/// Whether the chunk files match the world apart from its dirty chunks, only then can a
+/// save leave the other files alone.
+#[derive(Resource, Default)]
+struct SaveState {
+    synced: bool,
+}
+
+/// Everything that gets written to the map save, the points go in the chunk files.
+#[derive(Default, Deserialize, Serialize)]
+pub struct MapData {
+    /// Only in saves from before the chunk files.
+    #[serde(default)]
+    pub points: Vec<Point>,
+    #[serde(default)]
+    pub zones: Vec<ZonedCell>,
+    #[serde(default)]
+    pub budget: Budget,
+    /// In-game minutes, see [`SimClock`].
+    #[serde(default)]
+    pub minutes: u64,
+    #[serde(default)]
+    pub bookmarks: Vec<Bookmark>,
+    #[serde(default)]
+    pub grid: Grid,
+    #[serde(default)]
+    pub terrain: Vec<TerrainCell>,
+    #[serde(default)]
+    pub water: Vec<Position>,
+}
+
+/// Saves used to be a plain list of points, those still load as a map with only points.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SaveFile {
+    Map(MapData),
+    Points(Vec<Point>),
+}
+
+impl From<SaveFile> for MapData {
+    fn from(value: SaveFile) -> Self {
+        match value {
+            SaveFile::Map(map) => map,
+            SaveFile::Points(points) => Self {
+                points,
+                ..default()
+            },
+        }
     }
+}
+
+fn save_key(
+    actions: Res<Actions>,
+    mut world: ResMut<World>,
+    mut save_state: ResMut<SaveState>,
+    zones: Res<Zones>,
+    budget: Res<Budget>,
+    clock: Res<SimClock>,
+    bookmarks: Res<Bookmarks>,
+    grid: Res<Grid>,
+    terrain: Res<Terrain>,
+    water: Res<Water>,
+) {
+    if actions.just_pressed(Action::Save) {
+        let map = MapData {
+            points: vec![],
+            zones: zones.to_cells(),
+            budget: budget.clone(),
+            minutes: clock.minutes,
+            bookmarks: bookmarks.list.clone(),
+            grid: grid.clone(),
+            terrain: terrain.to_cells(),
+            water: water.cells.iter().copied().collect(),
+        };
 
-    if keys.pressed(KeyCode::ControlLeft) && keys.just_pressed(KeyCode::S) {
-        let content = serde_json::to_string(&points_vecs).unwrap();
+        // The chunks go first. Nothing counts as saved unless all of it was written, so the
+        // next save tries the same chunks again.
+        let saved = save_chunks(&mut world, save_state.synced).and_then(|written| {
+            write_file(
+                Path::new("./data.json"),
+                &serde_json::to_string(&map).unwrap(),
+            )?;
+            Ok(written)
+        });
 
-        fs::write("./data.json", content).expect("Data writen!");
+        match saved {
+            Ok(written) => {
+                save_state.synced = true;
+                info!("Saved {} changed chunks", written);
+            }
+            Err(err) => warn!("Couldn't save the map: {}", err),
+        }
     }
 }
 
 fn load_key(
     mut commands: Commands,
-    keys: Res<Input<KeyCode>>,
-    points: Query<Entity, With<Point>>,
-    asset_server: Res<AssetServer>,
+    actions: Res<Actions>,
+    mut world: ResMut<World>,
+    mut loaded: ResMut<LoadedChunks>,
+    mut save_state: ResMut<SaveState>,
+    mut zones: ResMut<Zones>,
+    mut budget: ResMut<Budget>,
+    mut clock: ResMut<SimClock>,
+    mut bookmarks: ResMut<Bookmarks>,
+    mut grid: ResMut<Grid>,
+    mut terrain: ResMut<Terrain>,
+    mut water: ResMut<Water>,
 ) {
-    let content = fs::read_to_string("./data.json").expect("Data for the BG menu not found!");
+    if actions.just_pressed(Action::Load) {
+        let content = fs::read_to_string("./data.json").expect("Data for the BG menu not found!");
+
+        let map: MapData = serde_json::from_str::<SaveFile>(&content).unwrap().into();
+
+        // Only the chunks around the camera get spawned again.
+        loaded.unload_all(&mut commands);
+
+        *grid = map.grid;
+        terrain.set_cells(&map.terrain);
+        water.cells = map.water.into_iter().collect();
+
+        if map.points.is_empty() {
+            load_chunks(&mut world);
+            save_state.synced = true;
+        } else {
+            // Saves from before the chunk files, the next save writes them all out.
+            world.clear();
+
+            for point in map.points {
+                world.insert(point);
+            }
+
+            save_state.synced = false;
+        }
+
+        zones.set_cells(&map.zones);
+        *budget = map.budget;
+        clock.set_minutes(map.minutes);
+        bookmarks.list = map.bookmarks;
+    }
+}
+
+fn chunk_file(chunk: &Position) -> PathBuf {
+    PathBuf::from(format!("{}_{}.json", chunk.x, chunk.y))
+}
+
+/// Writes `content` next to `path` first and renames it over `path`, so `path` is either
+/// the old or the new file even if writing fails halfway.
+fn write_file(path: &Path, content: &str) -> io::Result<()> {
+    let mut staged = path.as_os_str().to_owned();
+    staged.push(".tmp");
+
+    fs::write(&staged, content)?;
+    fs::rename(&staged, path)
+}
+
+/// Writes the chunks that changed since the last save, or all of them when the files on
+/// disk aren't `synced` with the world. Returns how many were written.
+///
+/// A full save writes a new folder and swaps it in at the end, the files of another map
+/// would be loaded along with this one otherwise.
+fn save_chunks(world: &mut World, synced: bool) -> io::Result<usize> {
+    let folder = if synced { CHUNKS_PATH } else { STAGING_PATH };
+
+    if !synced && Path::new(STAGING_PATH).exists() {
+        fs::remove_dir_all(STAGING_PATH)?;
+    }
+
+    fs::create_dir_all(folder)?;
+
+    let mut written = 0;
+
+    for (chunk, data) in world.chunks() {
+        if synced && !data.dirty {
+            continue;
+        }
+
+        let path = Path::new(folder).join(chunk_file(chunk));
+
+        if data.points.is_empty() {
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+
+            continue;
+        }
+
+        write_file(&path, &serde_json::to_string(&data.points).unwrap())?;
+        written += 1;
+    }
 
-    let points_data: Vec<Point> = serde_json::from_str(&content).unwrap();
+    if !synced {
+        if Path::new(BACKUP_PATH).exists() {
+            fs::remove_dir_all(BACKUP_PATH)?;
+        }
 
-    if keys.pressed(KeyCode::ControlLeft) && keys.just_pressed(KeyCode::L) {
-        for point in points.iter() {
-            commands.entity(point).despawn_recursive();
+        if Path::new(CHUNKS_PATH).exists() {
+            fs::rename(CHUNKS_PATH, BACKUP_PATH)?;
         }
 
-        for point in points_data.iter() {
-            let model: Handle<Scene> = asset_server.load(point.has.meta().path);
+        fs::rename(STAGING_PATH, CHUNKS_PATH)?;
 
-            let mut tf = Transform::from_xyz(
-                point.position.x as f32 * 20.,
-                0.,
-                point.position.y as f32 * 20.,
+        if let Err(err) = fs::remove_dir_all(BACKUP_PATH) {
+            warn!(
+                "Couldn't remove the previous chunks in {}: {}",
+                BACKUP_PATH, err
             );
+        }
+    }
+
+    world.mark_saved();
+
+    Ok(written)
+}
+
+fn load_chunks(world: &mut World) {
+    world.clear();
+
+    // A full save that stopped between swapping the folders only left the backup.
+    let folder = if !Path::new(CHUNKS_PATH).exists() && Path::new(BACKUP_PATH).exists() {
+        warn!("Loading the chunks from {}", BACKUP_PATH);
+        BACKUP_PATH
+    } else {
+        CHUNKS_PATH
+    };
+
+    let Ok(entries) = fs::read_dir(folder) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+
+        // Files left over from a save that didn't finish aren't chunks either.
+        let chunk = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_suffix(".json"))
+            .and_then(|stem| stem.split_once('_'))
+            .and_then(|(x, y)| Some(Position::new(x.parse().ok()?, y.parse().ok()?)));
+
+        let Some(chunk) = chunk else {
+            warn!("Skipping {}, it's not a chunk", path.display());
+            continue;
+        };
 
-            tf.rotation = Quat::from_rotation_y(point.orientation.rotation());
+        let points = fs::read_to_string(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|content| {
+                serde_json::from_str::<Vec<Point>>(&content).map_err(|err| err.to_string())
+            });
 
-            commands.spawn((
-                SceneBundle {
-                    scene: model,
-                    transform: tf,
-                    ..default()
-                },
-                point.clone(),
-            ));
+        match points {
+            Ok(points) => world.load_chunk(chunk, points),
+            Err(err) => warn!("Skipping {}, it can't be read: {}", path.display(), err),
         }
     }
 }
@@ -0,0 +1,328 @@
+use std::{collections::HashSet, f32::consts::TAU};
+
+use bevy::{pbr::CascadeShadowConfigBuilder, prelude::*};
+
+use crate::{
+    controls::{
+        input_map::{Action, Actions},
+        movement::MainCamera,
+    },
+    simulation::clock::SimClock,
+    world::point::Point,
+};
+
+use super::weather::WeatherState;
+
+/// Day and night cycle, moves the sun and dims the ambient light following the time of day,
+/// lights up the streets and windows after dusk.
+pub struct LightingPlugin;
+
+impl Plugin for LightingPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(AmbientLight {
+            color: Color::WHITE,
+            brightness: 1.0,
+        });
+        app.insert_resource(TimeOfDay::default());
+        app.insert_resource(Daylight::default());
+        app.insert_resource(LightingConfig::default());
+        app.insert_resource(WindowMaterials::default());
+
+        app.add_systems(Startup, setup);
+        app.add_systems(
+            Update,
+            (
+                follow_clock,
+                update_sun.after(follow_clock),
+                toggle_street_lights,
+                update_street_lights.after(update_sun),
+                find_window_materials,
+                update_windows.after(update_sun),
+            ),
+        );
+    }
+}
+
+/// `hours` Time of day the lighting is showing, from `0` to `24`.
+///
+/// `follow_clock` Whether `hours` is taken from the [`SimClock`], it's turned off when the
+/// time is set by hand.
+#[derive(Resource, Debug)]
+pub struct TimeOfDay {
+    pub hours: f32,
+    pub follow_clock: bool,
+}
+
+impl Default for TimeOfDay {
+    fn default() -> Self {
+        Self {
+            hours: 12.,
+            follow_clock: true,
+        }
+    }
+}
+
+/// How much daylight there is, `1` at day and `0` at night. Night lights use the inverse.
+#[derive(Resource, Debug, Default)]
+pub struct Daylight {
+    pub amount: f32,
+}
+
+impl Daylight {
+    pub fn night(&self) -> f32 {
+        1. - self.amount
+    }
+}
+
+/// `street_lights` Whether road tiles get a light, toggled with `N`.
+///
+/// `max_street_lights` and `street_light_distance` Only the road tiles closest to the camera
+/// and within that distance of it get one of the lights, the renderer can only take so many
+/// and the ones further out aren't worth it.
+#[derive(Resource, Debug)]
+pub struct LightingConfig {
+    pub sun_illuminance: f32,
+    pub street_lights: bool,
+    pub street_light_intensity: f32,
+    pub max_street_lights: usize,
+    pub street_light_distance: f32,
+    pub window_color: Color,
+}
+
+impl Default for LightingConfig {
+    fn default() -> Self {
+        Self {
+            sun_illuminance: 12_000.,
+            street_lights: false,
+            street_light_intensity: 3_000.,
+            max_street_lights: 48,
+            street_light_distance: 400.,
+            window_color: Color::rgb(1., 0.75, 0.4),
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Sun;
+
+/// One of the lights moved to whichever road tiles are closest to the camera.
+#[derive(Component)]
+pub struct StreetLight;
+
+/// Materials used by the building models.
+///
+/// The building textures don't have the windows on a material of their own, so the whole
+/// base colour texture is used as emissive map with a warm tint. That reads as lit windows
+/// against the darkened walls, but the walls glow faintly as well, until the models come
+/// with a window mask.
+///
+/// `glow` How lit the windows currently are, materials are only touched when it changes.
+#[derive(Resource, Default)]
+struct WindowMaterials {
+    handles: HashSet<Handle<StandardMaterial>>,
+    glow: f32,
+}
+
+fn mix(a: Color, b: Color, t: f32) -> Color {
+    Vec4::from(a).lerp(Vec4::from(b), t).into()
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0., 1.);
+
+    t * t * (3. - 2. * t)
+}
+
+fn setup(mut commands: Commands) {
+    commands.spawn((
+        DirectionalLightBundle {
+            directional_light: DirectionalLight {
+                shadows_enabled: true,
+                ..default()
+            },
+            cascade_shadow_config: CascadeShadowConfigBuilder {
+                first_cascade_far_bound: 150.,
+                maximum_distance: 800.,
+                ..default()
+            }
+            .into(),
+            ..default()
+        },
+        Sun,
+    ));
+}
+
+fn follow_clock(clock: Res<SimClock>, mut time_of_day: ResMut<TimeOfDay>) {
+    if time_of_day.follow_clock {
+        time_of_day.hours = clock.time_of_day();
+    }
+}
+
+fn update_sun(
+    time_of_day: Res<TimeOfDay>,
+    config: Res<LightingConfig>,
+    weather: Res<WeatherState>,
+    mut daylight: ResMut<Daylight>,
+    mut ambient: ResMut<AmbientLight>,
+    mut sun: Query<(&mut DirectionalLight, &mut Transform), With<Sun>>,
+) {
+    let (mut light, mut tf) = sun.single_mut();
+
+    // Rises in the east at 6, highest at 12 and sets in the west at 18.
+    let angle = (time_of_day.hours - 6.) / 24. * TAU;
+    let direction = Vec3::new(angle.cos(), angle.sin(), 0.35).normalize();
+    let elevation = angle.sin();
+
+    daylight.amount = smoothstep(-0.1, 0.2, elevation);
+
+    *tf = Transform::from_translation(direction).looking_at(Vec3::ZERO, Vec3::Y);
+
+    let sunset = Color::rgb(1., 0.55, 0.3);
+    let noon = Color::rgb(1., 0.97, 0.92);
+
+    light.color = mix(sunset, noon, (elevation / 0.5).clamp(0., 1.));
+    light.illuminance =
+        config.sun_illuminance * smoothstep(-0.02, 0.15, elevation) * weather.current.sunlight();
+    light.shadows_enabled = elevation > 0.;
+
+    ambient.color = mix(Color::rgb(0.4, 0.5, 0.8), Color::WHITE, daylight.amount);
+    ambient.brightness = 0.08 + 0.42 * daylight.amount;
+}
+
+fn toggle_street_lights(
+    mut commands: Commands,
+    actions: Res<Actions>,
+    mut config: ResMut<LightingConfig>,
+    lights: Query<Entity, With<StreetLight>>,
+) {
+    if !actions.just_pressed(Action::ToggleStreetLights) {
+        return;
+    }
+
+    config.street_lights = !config.street_lights;
+
+    if config.street_lights {
+        return;
+    }
+
+    for entity in lights.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn update_street_lights(
+    mut commands: Commands,
+    config: Res<LightingConfig>,
+    daylight: Res<Daylight>,
+    camera: Query<&GlobalTransform, With<MainCamera>>,
+    roads: Query<(&Point, &GlobalTransform)>,
+    mut lights: Query<(&mut PointLight, &mut Transform, &mut Visibility), With<StreetLight>>,
+) {
+    if !config.street_lights {
+        return;
+    }
+
+    let Ok(camera) = camera.get_single() else {
+        return;
+    };
+
+    let eye = camera.translation();
+    let max_distance = config.street_light_distance * config.street_light_distance;
+
+    let mut nearby: Vec<(f32, Vec3)> = roads
+        .iter()
+        .filter(|(point, _)| point.has.is_road())
+        .map(|(_, transform)| {
+            let position = transform.translation();
+
+            (position.distance_squared(eye), position + Vec3::Y * 8.)
+        })
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect();
+
+    // Only the closest ones matter, they don't have to be in order.
+    if nearby.len() > config.max_street_lights {
+        nearby.select_nth_unstable_by(config.max_street_lights, |a, b| a.0.total_cmp(&b.0));
+        nearby.truncate(config.max_street_lights);
+    }
+
+    let missing = config.max_street_lights.saturating_sub(lights.iter().len());
+
+    for _ in 0..missing {
+        commands.spawn((
+            PointLightBundle {
+                point_light: PointLight {
+                    color: Color::rgb(1., 0.85, 0.6),
+                    range: 30.,
+                    ..default()
+                },
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            StreetLight,
+        ));
+    }
+
+    let intensity = config.street_light_intensity * daylight.night();
+    let mut nearby = nearby.into_iter();
+
+    for (mut light, mut transform, mut visibility) in lights.iter_mut() {
+        let (wanted, position) = match nearby.next() {
+            Some((_, position)) => (Visibility::Inherited, position),
+            None => (Visibility::Hidden, transform.translation),
+        };
+
+        if *visibility != wanted {
+            *visibility = wanted;
+        }
+
+        light.intensity = intensity;
+        transform.translation = position;
+    }
+}
+
+fn find_window_materials(
+    mut window_materials: ResMut<WindowMaterials>,
+    added: Query<(Entity, &Handle<StandardMaterial>), Added<Handle<StandardMaterial>>>,
+    parents: Query<&Parent>,
+    points: Query<&Point>,
+) {
+    for (entity, material) in added.iter() {
+        let is_building = parents
+            .iter_ancestors(entity)
+            .any(|ancestor| matches!(points.get(ancestor), Ok(point) if point.has.is_building()));
+
+        if is_building {
+            window_materials.handles.insert(material.clone());
+        }
+    }
+}
+
+fn update_windows(
+    daylight: Res<Daylight>,
+    config: Res<LightingConfig>,
+    mut window_materials: ResMut<WindowMaterials>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let glow = smoothstep(0.3, 0.8, daylight.night());
+
+    if (glow - window_materials.glow).abs() < 0.01 && !window_materials.is_changed() {
+        return;
+    }
+
+    window_materials.glow = glow;
+
+    let emissive = config.window_color * glow;
+
+    for handle in window_materials.handles.iter() {
+        let Some(material) = materials.get_mut(handle) else {
+            continue;
+        };
+
+        if material.emissive_texture.is_none() {
+            material.emissive_texture = material.base_color_texture.clone();
+        }
+
+        material.emissive = emissive;
+    }
+}
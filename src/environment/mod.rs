@@ -0,0 +1,13 @@
+use bevy::prelude::*;
+
+pub mod lighting;
+pub mod weather;
+
+/// Lighting and everything else around the city that isn't placed by the player.
+pub struct EnvironmentPlugin;
+
+impl Plugin for EnvironmentPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((lighting::LightingPlugin, weather::WeatherPlugin));
+    }
+}
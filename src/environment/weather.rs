@@ -0,0 +1,478 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::{pbr::FogFalloff, prelude::*};
+
+use crate::{
+    controls::{
+        input_map::{Action, Actions},
+        movement::MainCamera,
+    },
+    simulation::clock::{SimClock, SimUpdate},
+    world::{
+        batching::TileBatch,
+        point::{Point, PointType},
+    },
+};
+
+use super::lighting::Daylight;
+
+/// Weather and seasons: fog, rain and snow particles around the camera, grass and trees
+/// tinted after the season and a [`TrafficSpeed`] that drops in rain, fog and snow.
+pub struct WeatherPlugin;
+
+impl Plugin for WeatherPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(WeatherState::default());
+        app.insert_resource(SeasonalMaterials::default());
+        app.insert_resource(TrafficSpeed::default());
+
+        app.add_systems(SimUpdate, (daily_weather, update_traffic_speed).chain());
+        app.add_systems(
+            Update,
+            (
+                cycle_weather,
+                update_fog,
+                update_precipitation,
+                fall_precipitation,
+                find_seasonal_materials,
+                tint_seasons,
+            ),
+        );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Weather {
+    #[default]
+    Clear,
+    Rain,
+    Fog,
+    Snow,
+}
+
+impl Weather {
+    pub fn next(&self) -> Self {
+        match self {
+            Self::Clear => Self::Rain,
+            Self::Rain => Self::Fog,
+            Self::Fog => Self::Snow,
+            Self::Snow => Self::Clear,
+        }
+    }
+
+    /// How fast traffic moves compared to clear weather.
+    pub fn traffic_speed(&self) -> f32 {
+        match self {
+            Self::Clear => 1.,
+            Self::Rain => 0.75,
+            Self::Fog => 0.85,
+            Self::Snow => 0.5,
+        }
+    }
+
+    /// How much of the sunlight gets through the clouds.
+    pub fn sunlight(&self) -> f32 {
+        match self {
+            Self::Clear => 1.,
+            Self::Rain => 0.35,
+            Self::Fog => 0.5,
+            Self::Snow => 0.6,
+        }
+    }
+
+    /// Distance where the fog starts and where it covers everything.
+    fn fog_range(&self) -> (f32, f32) {
+        match self {
+            Self::Clear => (600., 1500.),
+            Self::Rain => (150., 700.),
+            Self::Fog => (20., 250.),
+            Self::Snow => (80., 500.),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Season {
+    Spring,
+    Summer,
+    Autumn,
+    Winter,
+}
+
+impl Season {
+    pub fn from_month(month: u64) -> Self {
+        match month {
+            3..=5 => Self::Spring,
+            6..=8 => Self::Summer,
+            9..=11 => Self::Autumn,
+            _ => Self::Winter,
+        }
+    }
+
+    /// Chance out of 100 for each kind of weather other than clear on a given day.
+    fn weather_chances(&self) -> [(Weather, u64); 3] {
+        match self {
+            Self::Spring => [(Weather::Rain, 30), (Weather::Fog, 10), (Weather::Snow, 0)],
+            Self::Summer => [(Weather::Rain, 15), (Weather::Fog, 0), (Weather::Snow, 0)],
+            Self::Autumn => [(Weather::Rain, 30), (Weather::Fog, 20), (Weather::Snow, 0)],
+            Self::Winter => [(Weather::Rain, 5), (Weather::Fog, 15), (Weather::Snow, 30)],
+        }
+    }
+
+    fn grass_tint(&self) -> Color {
+        match self {
+            Self::Spring => Color::WHITE,
+            Self::Summer => Color::rgb(1., 0.95, 0.8),
+            Self::Autumn => Color::rgb(1.05, 0.9, 0.65),
+            Self::Winter => Color::rgb(1.2, 1.2, 1.25),
+        }
+    }
+
+    fn tree_tint(&self) -> Color {
+        match self {
+            Self::Spring => Color::rgb(1., 1.05, 1.),
+            Self::Summer => Color::WHITE,
+            Self::Autumn => Color::rgb(1.3, 0.8, 0.4),
+            Self::Winter => Color::rgb(1.1, 1.1, 1.15),
+        }
+    }
+}
+
+/// `automatic` Whether the weather changes by itself every day, turned off when it's picked
+/// by hand with `F3`. `Shift + F3` turns it back on.
+#[derive(Resource, Debug)]
+pub struct WeatherState {
+    pub current: Weather,
+    pub season: Season,
+    pub automatic: bool,
+}
+
+impl Default for WeatherState {
+    fn default() -> Self {
+        Self {
+            current: Weather::Clear,
+            season: Season::Winter,
+            automatic: true,
+        }
+    }
+}
+
+/// How fast traffic moves in the current weather compared to clear weather, simulations
+/// that move anything over the roads scale by it.
+#[derive(Resource, Debug)]
+pub struct TrafficSpeed(pub f32);
+
+impl Default for TrafficSpeed {
+    fn default() -> Self {
+        Self(1.)
+    }
+}
+
+#[derive(Component)]
+struct Precipitation;
+
+/// Materials of grass and tree models, tinted after the season.
+///
+/// `originals` The colour each material had before it was first tinted, tints are applied on
+/// top of it so every season can go back to it.
+///
+/// `applied` The season and weather the materials were last tinted for.
+#[derive(Resource, Default)]
+struct SeasonalMaterials {
+    grass: HashSet<Handle<StandardMaterial>>,
+    trees: HashSet<Handle<StandardMaterial>>,
+    originals: HashMap<Handle<StandardMaterial>, Color>,
+    applied: Option<(Season, Weather)>,
+}
+
+/// Cheap deterministic hash, enough to make up weather and particle positions.
+fn noise(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Same as [`noise`] but between `0` and `1`.
+fn noise_f32(seed: u64) -> f32 {
+    (noise(seed) % 10_000) as f32 / 10_000.
+}
+
+fn daily_weather(clock: Res<SimClock>, mut state: ResMut<WeatherState>) {
+    let (_, month, _) = clock.date();
+
+    state.season = Season::from_month(month);
+
+    if !state.automatic || !clock.every(SimClock::DAY) {
+        return;
+    }
+
+    let mut roll = noise(clock.minutes / SimClock::DAY) % 100;
+
+    state.current = Weather::Clear;
+
+    for (weather, chance) in state.season.weather_chances() {
+        if roll < chance {
+            state.current = weather;
+            break;
+        }
+
+        roll -= chance;
+    }
+}
+
+fn cycle_weather(actions: Res<Actions>, mut state: ResMut<WeatherState>) {
+    if actions.just_pressed(Action::AutomaticWeather) {
+        state.automatic = true;
+        return;
+    }
+
+    if !actions.just_pressed(Action::CycleWeather) {
+        return;
+    }
+
+    state.automatic = false;
+    state.current = state.current.next();
+}
+
+fn update_fog(
+    mut commands: Commands,
+    time: Res<Time>,
+    state: Res<WeatherState>,
+    daylight: Res<Daylight>,
+    mut cameras: Query<(Entity, Option<&mut FogSettings>), With<MainCamera>>,
+) {
+    let (start, end) = state.current.fog_range();
+
+    let night = Color::rgb(0.05, 0.06, 0.1);
+    let day = match state.current {
+        Weather::Clear => Color::rgb(0.7, 0.8, 0.95),
+        _ => Color::rgb(0.65, 0.67, 0.7),
+    };
+    let color: Color = Vec4::from(night)
+        .lerp(Vec4::from(day), daylight.amount)
+        .into();
+
+    for (entity, fog) in cameras.iter_mut() {
+        let Some(mut fog) = fog else {
+            commands.entity(entity).insert(FogSettings {
+                color,
+                falloff: FogFalloff::Linear { start, end },
+                ..default()
+            });
+            continue;
+        };
+
+        // Ease into the new weather instead of popping.
+        let t = (time.delta_seconds() * 0.5).min(1.);
+
+        fog.color = color;
+
+        if let FogFalloff::Linear {
+            start: fog_start,
+            end: fog_end,
+        } = &mut fog.falloff
+        {
+            *fog_start += (start - *fog_start) * t;
+            *fog_end += (end - *fog_end) * t;
+        }
+    }
+}
+
+/// Spawns the rain or snow particles when the weather starts and removes them when it ends.
+fn update_precipitation(
+    mut commands: Commands,
+    state: Res<WeatherState>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    particles: Query<Entity, With<Precipitation>>,
+    mut spawned: Local<Option<Weather>>,
+) {
+    let weather = match state.current {
+        Weather::Rain | Weather::Snow => Some(state.current),
+        _ => None,
+    };
+
+    if *spawned == weather {
+        return;
+    }
+
+    *spawned = weather;
+
+    for entity in particles.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    let Some(weather) = weather else {
+        return;
+    };
+
+    let (mesh, color) = match weather {
+        Weather::Snow => (shape::Box::new(0.5, 0.5, 0.5), Color::WHITE),
+        _ => (
+            shape::Box::new(0.08, 2.5, 0.08),
+            Color::rgba(0.7, 0.75, 0.85, 0.6),
+        ),
+    };
+
+    let mesh = meshes.add(mesh.into());
+    let material = materials.add(StandardMaterial {
+        base_color: color,
+        unlit: true,
+        alpha_mode: AlphaMode::Blend,
+        ..default()
+    });
+
+    for i in 0..PARTICLES {
+        commands.spawn((
+            PbrBundle {
+                mesh: mesh.clone(),
+                material: material.clone(),
+                transform: Transform::from_xyz(
+                    (noise_f32(i * 3) - 0.5) * PARTICLE_AREA.x,
+                    noise_f32(i * 3 + 1) * PARTICLE_AREA.y,
+                    (noise_f32(i * 3 + 2) - 0.5) * PARTICLE_AREA.z,
+                ),
+                ..default()
+            },
+            Precipitation,
+        ));
+    }
+}
+
+const PARTICLES: u64 = 800;
+
+/// Size of the box around the camera the particles fall in.
+const PARTICLE_AREA: Vec3 = Vec3::new(240., 160., 240.);
+
+/// Moves the particles down, wrapping them around the box centered under the camera so they
+/// are always around what's being looked at.
+fn fall_precipitation(
+    time: Res<Time>,
+    state: Res<WeatherState>,
+    camera: Query<&GlobalTransform, With<MainCamera>>,
+    mut particles: Query<&mut Transform, With<Precipitation>>,
+) {
+    let Ok(camera) = camera.get_single() else {
+        return;
+    };
+
+    let velocity = match state.current {
+        Weather::Snow => Vec3::new(3., -12., 1.5),
+        _ => Vec3::new(2., -90., 0.),
+    };
+
+    let center = camera.translation() * Vec3::new(1., 0., 1.);
+    let half = PARTICLE_AREA * Vec3::new(0.5, 0., 0.5);
+
+    for mut tf in particles.iter_mut() {
+        tf.translation += velocity * time.delta_seconds();
+
+        if tf.translation.y < 0. {
+            tf.translation.y += PARTICLE_AREA.y;
+        }
+
+        let offset = tf.translation - center;
+
+        if offset.x > half.x {
+            tf.translation.x -= PARTICLE_AREA.x;
+        } else if offset.x < -half.x {
+            tf.translation.x += PARTICLE_AREA.x;
+        }
+
+        if offset.z > half.z {
+            tf.translation.z -= PARTICLE_AREA.z;
+        } else if offset.z < -half.z {
+            tf.translation.z += PARTICLE_AREA.z;
+        }
+    }
+}
+
+fn update_traffic_speed(state: Res<WeatherState>, mut traffic: ResMut<TrafficSpeed>) {
+    let speed = state.current.traffic_speed();
+
+    if traffic.0 != speed {
+        traffic.0 = speed;
+    }
+}
+
+fn find_seasonal_materials(
+    mut seasonal: ResMut<SeasonalMaterials>,
+    added: Query<(Entity, &Handle<StandardMaterial>), Added<Handle<StandardMaterial>>>,
+    parents: Query<&Parent>,
+    points: Query<&Point>,
+    batches: Query<&TileBatch>,
+) {
+    for (entity, material) in added.iter() {
+        // Batched tiles carry their type themselves, scenes have it on the point above.
+        let has = match batches.get(entity) {
+            Ok(batch) => Some(&batch.0),
+            Err(_) => parents
+                .iter_ancestors(entity)
+                .find_map(|ancestor| points.get(ancestor).ok())
+                .map(|point| &point.has),
+        };
+
+        let Some(has) = has else {
+            continue;
+        };
+
+        // Scenes of the same model share their materials, only new ones need a tint.
+        let new = if *has == PointType::Grass {
+            seasonal.grass.insert(material.clone())
+        } else if has.is_nature() {
+            seasonal.trees.insert(material.clone())
+        } else {
+            false
+        };
+
+        if new {
+            seasonal.applied = None;
+        }
+    }
+}
+
+fn tint_seasons(
+    state: Res<WeatherState>,
+    mut seasonal: ResMut<SeasonalMaterials>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let current = (state.season, state.current);
+
+    if seasonal.applied == Some(current) {
+        return;
+    }
+
+    seasonal.applied = Some(current);
+
+    // Snow covers everything a bit more than the winter tint alone.
+    let snow = match state.current {
+        Weather::Snow => 1.15,
+        _ => 1.,
+    };
+
+    let SeasonalMaterials {
+        grass,
+        trees,
+        originals,
+        ..
+    } = &mut *seasonal;
+
+    for (handles, tint) in [
+        (grass, state.season.grass_tint()),
+        (trees, state.season.tree_tint()),
+    ] {
+        let [r, g, b, _] = tint.as_rgba_f32();
+        let factor = Vec3::new(r, g, b) * snow;
+
+        for handle in handles.iter() {
+            if let Some(material) = materials.get_mut(handle) {
+                let original = *originals
+                    .entry(handle.clone())
+                    .or_insert(material.base_color);
+
+                material.base_color = original.as_rgba() * factor;
+            }
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use bevy::prelude::*;
+
+use crate::{
+    environment::weather::WeatherState,
+    simulation::clock::{SimClock, SimSpeed},
+};
+
+/// Shows the in-game date, time, weather and simulation speed in the top left corner.
+pub struct ClockDisplayPlugin;
+
+impl Plugin for ClockDisplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup);
+        app.add_systems(Update, update_clock_text);
+    }
+}
+
+#[derive(Component)]
+struct ClockText;
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("./fonts/Roboto-Regular.ttf");
+
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font,
+                font_size: 18.,
+                color: Color::WHITE,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.),
+            left: Val::Px(10.),
+            ..default()
+        }),
+        ClockText,
+    ));
+}
+
+fn update_clock_text(
+    clock: Res<SimClock>,
+    weather: Res<WeatherState>,
+    mut text: Query<&mut Text, With<ClockText>>,
+) {
+    if !clock.is_changed() && !weather.is_changed() {
+        return;
+    }
+
+    let (year, month, day) = clock.date();
+    let minutes = clock.minutes % SimClock::DAY;
+
+    let speed = if clock.paused {
+        "Paused"
+    } else {
+        match clock.speed {
+            SimSpeed::Normal => "1x",
+            SimSpeed::Fast => "2x",
+            SimSpeed::Fastest => "4x",
+        }
+    };
+
+    text.single_mut().sections[0].value = format!(
+        "Year {} Month {:02} Day {:02}  {:02}:{:02}  {:?}, {:?}  {}",
+        year,
+        month,
+        day,
+        minutes / SimClock::HOUR,
+        minutes % SimClock::HOUR,
+        weather.season,
+        weather.current,
+        speed
+    );
+}
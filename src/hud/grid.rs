@@ -0,0 +1,174 @@
+use bevy::prelude::*;
+
+use crate::{
+    controls::{
+        input_map::{Action, Actions},
+        mouse_projection::MouseProjection,
+        movement::ControlData,
+    },
+    world::grid::Grid,
+};
+
+/// Lines along the cell borders around the camera, toggled with `G`, and a readout of the
+/// cell under the cursor in the bottom left corner.
+pub struct GridOverlayPlugin;
+
+impl Plugin for GridOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(GridOverlay::default());
+        app.add_systems(Startup, setup);
+        app.add_systems(Update, (toggle_grid, draw_grid, update_readout));
+    }
+}
+
+/// `major_every` Every how many cells a line is drawn brighter.
+///
+/// `fade_radii` How far from the pivot, in camera radii, the lines fade out.
+#[derive(Resource, Debug)]
+pub struct GridOverlay {
+    pub visible: bool,
+    pub major_every: i32,
+    pub fade_radii: f32,
+    pub color: Color,
+    pub major_color: Color,
+}
+
+impl Default for GridOverlay {
+    fn default() -> Self {
+        Self {
+            visible: false,
+            major_every: 5,
+            fade_radii: 1.5,
+            color: Color::rgba(1., 1., 1., 0.25),
+            major_color: Color::rgba(1., 1., 1., 0.6),
+        }
+    }
+}
+
+#[derive(Component)]
+struct Readout;
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("./fonts/Roboto-Regular.ttf");
+
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font,
+                font_size: 16.,
+                color: Color::WHITE,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(10.),
+            left: Val::Px(10.),
+            ..default()
+        }),
+        Readout,
+    ));
+}
+
+fn toggle_grid(actions: Res<Actions>, mut overlay: ResMut<GridOverlay>) {
+    if actions.just_pressed(Action::ToggleGrid) {
+        overlay.visible = !overlay.visible;
+    }
+}
+
+fn draw_grid(
+    overlay: Res<GridOverlay>,
+    grid: Res<Grid>,
+    control_data: Res<ControlData>,
+    mut gizmos: Gizmos,
+) {
+    if !overlay.visible {
+        return;
+    }
+
+    let size = grid.cell_size;
+    let origin = grid.origin + Vec3::Y * 0.1;
+    let pivot = control_data.current.pivot;
+    // The pivot in the grid's own space, from its origin.
+    let center = pivot - grid.origin;
+    let fade = control_data.current.radius * overlay.fade_radii;
+
+    // Cells are centered on multiples of the size, so the borders sit half a cell off.
+    let cells = (fade / size).ceil() as i32 + 1;
+    let first = Vec2::new(
+        (center.x / size).round() - cells as f32,
+        (center.z / size).round() - cells as f32,
+    )
+    .as_ivec2();
+
+    let faded = |color: Color, point: Vec3| {
+        let distance = Vec2::new(point.x - pivot.x, point.z - pivot.z).length();
+
+        color.with_a(color.a() * (1. - distance / fade).max(0.))
+    };
+
+    for i in 0..=cells * 2 {
+        for (axis, line) in [(0, first.x + i), (1, first.y + i)] {
+            let color = if line.rem_euclid(overlay.major_every) == 0 {
+                overlay.major_color
+            } else {
+                overlay.color
+            };
+
+            let border = (line as f32 - 0.5) * size;
+
+            // One segment per cell so the fade follows the distance along the line.
+            for j in 0..cells * 2 {
+                let along = [
+                    (first[1 - axis] + j) as f32 - 0.5,
+                    (first[1 - axis] + j + 1) as f32 - 0.5,
+                ]
+                .map(|a| a * size);
+
+                let [start, end] = along.map(|a| match axis {
+                    0 => origin + Vec3::new(border, 0., a),
+                    _ => origin + Vec3::new(a, 0., border),
+                });
+
+                gizmos.line_gradient(start, end, faded(color, start), faded(color, end));
+            }
+        }
+    }
+
+    gizmos
+        .circle(origin, Vec3::Y, size * 0.25, Color::RED)
+        .segments(16);
+    gizmos.line(
+        origin - Vec3::X * size * 0.5,
+        origin + Vec3::X * size * 0.5,
+        Color::RED,
+    );
+    gizmos.line(
+        origin - Vec3::Z * size * 0.5,
+        origin + Vec3::Z * size * 0.5,
+        Color::BLUE,
+    );
+}
+
+fn update_readout(
+    mouse_projection: Res<MouseProjection>,
+    mut text: Query<&mut Text, With<Readout>>,
+) {
+    if !mouse_projection.is_changed() {
+        return;
+    }
+
+    let value = if mouse_projection.hit {
+        let cell = mouse_projection.normal;
+        let position = mouse_projection.position;
+
+        format!(
+            "Cell {}, {}  World {:.1}, {:.1}, {:.1}",
+            cell.x, cell.y, position.x, position.y, position.z
+        )
+    } else {
+        String::new()
+    };
+
+    text.single_mut().sections[0].value = value;
+}
@@ -0,0 +1,239 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::{prelude::*, render::primitives::Aabb};
+
+use crate::{
+    controls::{
+        mouse_projection::MouseProjection, movement::MainCamera, place_model::Orientation,
+        walk::CameraView,
+    },
+    simulation::zoning::Zones,
+    world::{
+        grid::Grid,
+        point::{Layer, Point, Position},
+        roads::RoadNetwork,
+        terrain::Terrain,
+        water::Water,
+        World,
+    },
+};
+
+use super::PointerOverUi;
+
+/// Outlines the models on the cell under the cursor and shows what they are next to it.
+pub struct HoverPlugin;
+
+impl Plugin for HoverPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Hovered::default());
+        app.add_systems(Startup, setup);
+        app.add_systems(
+            Update,
+            (update_hovered, draw_highlight, update_tooltip)
+                .chain()
+                .run_if(in_state(CameraView::Orbit)),
+        );
+        app.add_systems(OnEnter(CameraView::Walk), clear_hovered);
+    }
+}
+
+/// The cell of the placed model the cursor is on, `None` over bare ground or the sky.
+#[derive(Resource, Default, Debug)]
+pub struct Hovered {
+    pub cell: Option<Position>,
+}
+
+#[derive(Component)]
+struct Tooltip;
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("./fonts/Roboto-Regular.ttf");
+
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font,
+                font_size: 14.,
+                color: Color::WHITE,
+            },
+        )
+        .with_style(Style {
+            display: Display::None,
+            position_type: PositionType::Absolute,
+            padding: UiRect::all(Val::Px(6.)),
+            ..default()
+        })
+        .with_background_color(Color::rgba(0., 0., 0., 0.7)),
+        Tooltip,
+    ));
+}
+
+fn update_hovered(
+    mouse_projection: Res<MouseProjection>,
+    pointer_over_ui: Res<PointerOverUi>,
+    world: Res<World>,
+    mut hovered: ResMut<Hovered>,
+) {
+    // Batched floor tiles have no meshes of their own to hit, the ground under them counts.
+    let on_model =
+        mouse_projection.entity.is_some() || world.get_point(&mouse_projection.normal).is_some();
+
+    let cell = match on_model {
+        true if mouse_projection.hit && !pointer_over_ui.0 => Some(mouse_projection.normal),
+        _ => None,
+    };
+
+    if hovered.cell != cell {
+        hovered.cell = cell;
+    }
+}
+
+fn clear_hovered(mut hovered: ResMut<Hovered>, mut tooltip: Query<&mut Style, With<Tooltip>>) {
+    hovered.cell = None;
+    tooltip.single_mut().display = Display::None;
+}
+
+fn draw_highlight(
+    hovered: Res<Hovered>,
+    grid: Res<Grid>,
+    points: Query<(Entity, &Point, &GlobalTransform)>,
+    children: Query<&Children>,
+    meshes: Query<(&Aabb, &GlobalTransform)>,
+    mut gizmos: Gizmos,
+) {
+    let Some(cell) = hovered.cell else {
+        return;
+    };
+
+    for (entity, point, point_transform) in points.iter() {
+        if point.position != cell {
+            continue;
+        }
+
+        let color = match point.has.layer() {
+            Layer::Floor => Color::rgb(0.9, 0.9, 0.9),
+            Layer::Building => Color::YELLOW,
+            Layer::Nature => Color::GREEN,
+        };
+
+        let mut outlined = false;
+
+        for descendant in children.iter_descendants(entity) {
+            let Ok((aabb, transform)) = meshes.get(descendant) else {
+                continue;
+            };
+
+            outlined = true;
+
+            let bounds = Transform::from_translation(aabb.center.into())
+                .with_scale(Vec3::from(aabb.half_extents) * 2.);
+
+            gizmos.cuboid(transform.mul_transform(bounds), color);
+        }
+
+        if !outlined {
+            // Batched tiles have no meshes of their own, they get the cell they cover.
+            gizmos.rect(
+                point_transform.translation() + Vec3::Y * 0.2,
+                Quat::from_rotation_x(FRAC_PI_2),
+                Vec2::splat(grid.cell_size),
+                color,
+            );
+        }
+    }
+}
+
+fn update_tooltip(
+    hovered: Res<Hovered>,
+    world: Res<World>,
+    zones: Res<Zones>,
+    terrain: Res<Terrain>,
+    water: Res<Water>,
+    roads: Res<RoadNetwork>,
+    mouse_projection: Res<MouseProjection>,
+    camera_q: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut tooltip: Query<(&mut Text, &mut Style), With<Tooltip>>,
+) {
+    let (mut text, mut style) = tooltip.single_mut();
+
+    let Some(cell) = hovered.cell else {
+        style.display = Display::None;
+        return;
+    };
+
+    let (camera, camera_transform) = camera_q.single();
+
+    // Follows the hit rather than the window cursor so it works for the gamepad too.
+    let Some(screen) = camera.world_to_viewport(camera_transform, mouse_projection.position) else {
+        style.display = Display::None;
+        return;
+    };
+
+    style.display = Display::Flex;
+    style.left = Val::Px(screen.x + 16.);
+    style.top = Val::Px(screen.y + 16.);
+
+    if !hovered.is_changed()
+        && !world.is_changed()
+        && !zones.is_changed()
+        && !terrain.is_changed()
+        && !water.is_changed()
+        && !roads.is_changed()
+    {
+        return;
+    }
+
+    let mut lines = vec![format!("Cell {}, {}", cell.x, cell.y)];
+
+    let elevation = terrain.get(&cell);
+
+    match elevation.ramp {
+        Some(side) => lines.push(format!(
+            "Level {}, ramp up {}",
+            elevation.level,
+            Orientation::index(side).name()
+        )),
+        None if elevation.level != 0 => lines.push(format!("Level {}", elevation.level)),
+        None => {}
+    }
+
+    if water.contains(&cell) {
+        lines.push("Water".to_string());
+    }
+
+    if let Some(zone) = zones.get(&cell) {
+        lines.push(format!("Zone {:?}", zone));
+    }
+
+    for point in world.points_at(&cell) {
+        let meta = point.has.meta();
+
+        lines.push(format!(
+            "{:?} ({:?}) facing {}",
+            point.has,
+            point.has.layer(),
+            point.orientation.name()
+        ));
+
+        if meta.residents > 0 || meta.jobs > 0 {
+            lines.push(format!(
+                "  Residents {}  Jobs {}",
+                meta.residents, meta.jobs
+            ));
+        }
+
+        if meta.upkeep > 0 {
+            lines.push(format!("  Upkeep {}", meta.upkeep));
+        }
+
+        if point.has.is_road() {
+            lines.push(format!(
+                "  Road network {} cells",
+                roads.network_size(&cell)
+            ));
+        }
+    }
+
+    text.sections[0].value = lines.join("\n");
+}
@@ -0,0 +1,76 @@
+use bevy::prelude::*;
+
+use crate::models::{ModelCache, ModelsState};
+
+/// Covers the screen until every model of the catalog is loaded, so nothing pops in later.
+pub struct LoadingScreenPlugin;
+
+impl Plugin for LoadingScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup);
+        app.add_systems(
+            Update,
+            update_loading_text.run_if(in_state(ModelsState::Loading)),
+        );
+        app.add_systems(OnExit(ModelsState::Loading), close);
+    }
+}
+
+#[derive(Component)]
+struct LoadingScreen;
+
+#[derive(Component)]
+struct LoadingText;
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("./fonts/Roboto-Regular.ttf");
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                background_color: Color::rgb(0.08, 0.08, 0.1).into(),
+                z_index: ZIndex::Global(100),
+                ..default()
+            },
+            // Keeps clicks from reaching the world while it's up.
+            Interaction::default(),
+            LoadingScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "Loading models",
+                    TextStyle {
+                        font,
+                        font_size: 28.,
+                        color: Color::WHITE,
+                    },
+                ),
+                LoadingText,
+            ));
+        });
+}
+
+fn update_loading_text(
+    asset_server: Res<AssetServer>,
+    cache: Res<ModelCache>,
+    mut text: Query<&mut Text, With<LoadingText>>,
+) {
+    let (done, total) = cache.progress(&asset_server);
+
+    text.single_mut().sections[0].value = format!("Loading models {}/{}", done, total);
+}
+
+fn close(mut commands: Commands, screen: Query<Entity, With<LoadingScreen>>) {
+    for entity in screen.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,49 @@
+use bevy::prelude::*;
+
+use crate::controls::gamepad::InputDevice;
+
+pub mod clock;
+pub mod grid;
+pub mod hover;
+pub mod loading;
+pub mod palette;
+pub mod stats;
+pub mod thumbnails;
+pub mod time_of_day;
+
+/// On screen panels and readouts drawn over the world.
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(PointerOverUi::default());
+        app.add_systems(PreUpdate, update_pointer_over_ui);
+
+        app.add_plugins((
+            clock::ClockDisplayPlugin,
+            grid::GridOverlayPlugin,
+            hover::HoverPlugin,
+            loading::LoadingScreenPlugin,
+            palette::PalettePlugin,
+            stats::StatsPanelPlugin,
+            thumbnails::ThumbnailPlugin,
+            time_of_day::TimeOfDayPlugin,
+        ));
+    }
+}
+
+/// Whether the mouse is over a panel, clicks there shouldn't reach the world.
+#[derive(Resource, Default, Debug)]
+pub struct PointerOverUi(pub bool);
+
+fn update_pointer_over_ui(
+    interactions: Query<&Interaction>,
+    device: Res<InputDevice>,
+    mut over_ui: ResMut<PointerOverUi>,
+) {
+    // The gamepad cursor is on the grid, wherever the mouse was left.
+    over_ui.0 = *device == InputDevice::Mouse
+        && interactions
+            .iter()
+            .any(|interaction| *interaction != Interaction::None);
+}
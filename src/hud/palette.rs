@@ -0,0 +1,468 @@
+use std::fs;
+
+use bevy::{prelude::*, ui::FocusPolicy};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    controls::{
+        input_map::{Action, Actions, Typing},
+        model_cursor::ModelCursor,
+        place_model::PlaceDelta,
+    },
+    simulation::zoning::Zone,
+    world::terrain::TerrainTool,
+};
+
+use super::thumbnails::Thumbnails;
+
+/// Panel on the left listing everything that can be placed, grouped by category, toggled
+/// with `F2`.
+///
+/// Clicking an entry selects it like the palette keys do, the star next to it adds it to
+/// the favourites at the top. Clicking the search box and typing filters the entries by
+/// name, category and tags, key bindings are off until `Enter`, `Escape` or a click
+/// elsewhere.
+pub struct PalettePlugin;
+
+impl Plugin for PalettePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Favourites::load(Favourites::PATH));
+        app.insert_resource(PaletteSearch::default());
+        app.add_systems(Startup, setup);
+        app.add_systems(
+            Update,
+            (
+                toggle_palette,
+                focus_search,
+                type_search,
+                select_entry,
+                toggle_favourite,
+                update_entries,
+            )
+                .chain(),
+        );
+    }
+}
+
+/// Names of the palette entries starred by the user, kept in `./favourites.json`.
+#[derive(Resource, Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Favourites {
+    pub names: Vec<String>,
+}
+
+impl Favourites {
+    pub const PATH: &'static str = "./favourites.json";
+
+    /// Reads the favourites from `path`, none when there's no file yet.
+    pub fn load(path: &str) -> Self {
+        let Ok(content) = fs::read_to_string(path) else {
+            return Self::default();
+        };
+
+        serde_json::from_str(&content).unwrap_or_else(|err| {
+            warn!("Invalid favourites in {}, starting without: {}", path, err);
+            Self::default()
+        })
+    }
+
+    pub fn save(&self, path: &str) {
+        if let Err(err) = fs::write(path, serde_json::to_string_pretty(self).unwrap()) {
+            warn!("Couldn't write the favourites to {}: {}", path, err);
+        }
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.names.iter().any(|favourite| favourite == name)
+    }
+
+    pub fn toggle(&mut self, name: &str) {
+        if self.contains(name) {
+            self.names.retain(|favourite| favourite != name);
+        } else {
+            self.names.push(name.to_string());
+        }
+    }
+}
+
+/// `query` What the entries are filtered by, `focused` whether typing goes into it.
+#[derive(Resource, Debug, Default)]
+struct PaletteSearch {
+    query: String,
+    focused: bool,
+}
+
+impl PaletteSearch {
+    fn matches(&self, cursor: &ModelCursor) -> bool {
+        let query = self.query.trim().to_lowercase();
+
+        if query.is_empty() {
+            return true;
+        }
+
+        cursor.name().to_lowercase().contains(&query)
+            || cursor.category().to_lowercase().contains(&query)
+            || cursor.meta().tags.iter().any(|tag| tag.contains(&query))
+    }
+}
+
+#[derive(Component)]
+struct Palette;
+
+#[derive(Component)]
+struct SearchBox;
+
+#[derive(Component)]
+struct SearchText;
+
+#[derive(Component)]
+struct PaletteList;
+
+#[derive(Component)]
+struct PaletteEntry(ModelCursor);
+
+#[derive(Component)]
+struct FavouriteButton(String);
+
+#[derive(Resource)]
+struct PaletteFont(Handle<Font>);
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("./fonts/Roboto-Regular.ttf");
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(40.),
+                    bottom: Val::Px(40.),
+                    left: Val::Px(10.),
+                    width: Val::Px(240.),
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(8.)),
+                    row_gap: Val::Px(6.),
+                    overflow: Overflow::clip(),
+                    ..default()
+                },
+                background_color: Color::rgba(0., 0., 0., 0.6).into(),
+                ..default()
+            },
+            Interaction::default(),
+            Palette,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    NodeBundle {
+                        style: Style {
+                            padding: UiRect::all(Val::Px(4.)),
+                            ..default()
+                        },
+                        background_color: Color::rgba(1., 1., 1., 0.1).into(),
+                        focus_policy: FocusPolicy::Block,
+                        ..default()
+                    },
+                    Interaction::default(),
+                    SearchBox,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font: font.clone(),
+                                font_size: 14.,
+                                color: Color::WHITE,
+                            },
+                        ),
+                        SearchText,
+                    ));
+                });
+
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(2.),
+                        ..default()
+                    },
+                    ..default()
+                },
+                PaletteList,
+            ));
+        });
+
+    commands.insert_resource(PaletteFont(font));
+}
+
+fn toggle_palette(
+    actions: Res<Actions>,
+    mut search: ResMut<PaletteSearch>,
+    mut palette: Query<&mut Style, With<Palette>>,
+) {
+    if !actions.just_pressed(Action::TogglePalette) {
+        return;
+    }
+
+    let mut style = palette.single_mut();
+
+    style.display = match style.display {
+        Display::None => Display::Flex,
+        _ => Display::None,
+    };
+
+    search.focused = false;
+}
+
+fn focus_search(
+    buttons: Res<Input<MouseButton>>,
+    search_box: Query<&Interaction, With<SearchBox>>,
+    mut search: ResMut<PaletteSearch>,
+) {
+    if !buttons.just_pressed(MouseButton::Left) {
+        return;
+    }
+
+    let focused = *search_box.single() == Interaction::Pressed;
+
+    if search.focused != focused {
+        search.focused = focused;
+    }
+}
+
+fn type_search(
+    mut characters: EventReader<ReceivedCharacter>,
+    keys: Res<Input<KeyCode>>,
+    mut search: ResMut<PaletteSearch>,
+    mut typing: ResMut<Typing>,
+    mut text: Query<&mut Text, With<SearchText>>,
+) {
+    // Read every frame so characters typed before focusing don't show up later.
+    let typed: String = characters
+        .iter()
+        .map(|event| event.char)
+        .filter(|char| !char.is_control())
+        .collect();
+
+    if search.focused {
+        if !typed.is_empty() {
+            search.query.push_str(&typed);
+        }
+
+        if keys.just_pressed(KeyCode::Back) {
+            search.query.pop();
+        }
+
+        if keys.any_just_pressed([KeyCode::Return, KeyCode::Escape]) {
+            search.focused = false;
+        }
+    }
+
+    if !search.is_changed() {
+        return;
+    }
+
+    // Only when the focus could have moved, other text fields own the flag otherwise.
+    if typing.0 != search.focused {
+        typing.0 = search.focused;
+    }
+
+    let section = &mut text.single_mut().sections[0];
+
+    (section.value, section.style.color) = match (search.focused, search.query.is_empty()) {
+        (false, true) => ("Search models and tags".to_string(), Color::GRAY),
+        (true, _) => (format!("{}|", search.query), Color::WHITE),
+        (false, false) => (search.query.clone(), Color::WHITE),
+    };
+}
+
+fn select_entry(
+    entries: Query<(&Interaction, &PaletteEntry), Changed<Interaction>>,
+    mut model_cursor: ResMut<ModelCursor>,
+    mut place_delta: ResMut<PlaceDelta>,
+) {
+    for (interaction, entry) in entries.iter() {
+        if *interaction == Interaction::Pressed {
+            *model_cursor = entry.0.clone();
+            *place_delta = PlaceDelta::Update;
+        }
+    }
+}
+
+fn toggle_favourite(
+    buttons: Query<(&Interaction, &FavouriteButton), Changed<Interaction>>,
+    mut favourites: ResMut<Favourites>,
+) {
+    for (interaction, button) in buttons.iter() {
+        if *interaction == Interaction::Pressed {
+            favourites.toggle(&button.0);
+            favourites.save(Favourites::PATH);
+        }
+    }
+}
+
+/// Colour shown in place of a picture for entries without a model of their own, and for
+/// models whose picture isn't rendered yet.
+fn swatch(cursor: &ModelCursor) -> Color {
+    match cursor {
+        ModelCursor::Zones(index) => Zone::index(*index).color(),
+        ModelCursor::Terrain(index) => match TerrainTool::index(*index) {
+            TerrainTool::Water => Color::rgb(0.12, 0.38, 0.58),
+            _ => Color::rgb(0.45, 0.36, 0.25),
+        },
+        _ => Color::rgba(1., 1., 1., 0.1),
+    }
+}
+
+/// Lists the entries again whenever the search, selection, favourites or pictures change.
+fn update_entries(
+    mut commands: Commands,
+    font: Res<PaletteFont>,
+    search: Res<PaletteSearch>,
+    model_cursor: Res<ModelCursor>,
+    favourites: Res<Favourites>,
+    thumbnails: Res<Thumbnails>,
+    list: Query<Entity, With<PaletteList>>,
+) {
+    if !search.is_changed()
+        && !model_cursor.is_changed()
+        && !favourites.is_changed()
+        && !thumbnails.is_changed()
+    {
+        return;
+    }
+
+    let entries: Vec<ModelCursor> = ModelCursor::all()
+        .into_iter()
+        .filter(|cursor| search.matches(cursor))
+        .collect();
+
+    let starred: Vec<ModelCursor> = entries
+        .iter()
+        .filter(|cursor| favourites.contains(&cursor.name()))
+        .cloned()
+        .collect();
+
+    // Entries come in category order, so each category is one run of them.
+    let mut groups: Vec<(&'static str, Vec<ModelCursor>)> = vec![("Favourites", starred)];
+
+    for cursor in entries {
+        match groups.last_mut() {
+            Some((category, list)) if *category == cursor.category() => list.push(cursor),
+            _ => groups.push((cursor.category(), vec![cursor])),
+        }
+    }
+
+    let header_style = TextStyle {
+        font: font.0.clone(),
+        font_size: 12.,
+        color: Color::GRAY,
+    };
+
+    let name_style = TextStyle {
+        font: font.0.clone(),
+        font_size: 14.,
+        color: Color::WHITE,
+    };
+
+    commands
+        .entity(list.single())
+        .despawn_descendants()
+        .with_children(|parent| {
+            for (category, cursors) in groups.into_iter().filter(|(_, list)| !list.is_empty()) {
+                parent.spawn(TextBundle::from_section(category, header_style.clone()));
+
+                for cursor in cursors {
+                    let name = cursor.name();
+                    let selected = cursor == *model_cursor;
+                    let starred = favourites.contains(&name);
+
+                    let picture = match cursor {
+                        ModelCursor::Zones(_) | ModelCursor::Terrain(_) => None,
+                        _ => thumbnails.get(cursor.meta().path),
+                    };
+
+                    let background = if selected {
+                        Color::rgba(1., 1., 1., 0.25)
+                    } else {
+                        Color::NONE
+                    };
+
+                    parent
+                        .spawn((
+                            NodeBundle {
+                                style: Style {
+                                    align_items: AlignItems::Center,
+                                    column_gap: Val::Px(6.),
+                                    padding: UiRect::all(Val::Px(2.)),
+                                    ..default()
+                                },
+                                background_color: background.into(),
+                                focus_policy: FocusPolicy::Block,
+                                ..default()
+                            },
+                            Interaction::default(),
+                            PaletteEntry(cursor.clone()),
+                        ))
+                        .with_children(|parent| {
+                            let size = Style {
+                                width: Val::Px(32.),
+                                height: Val::Px(32.),
+                                ..default()
+                            };
+
+                            match picture {
+                                Some(image) => parent.spawn(ImageBundle {
+                                    style: size,
+                                    image: UiImage::new(image),
+                                    ..default()
+                                }),
+                                None => parent.spawn(NodeBundle {
+                                    style: size,
+                                    background_color: swatch(&cursor).into(),
+                                    ..default()
+                                }),
+                            };
+
+                            parent.spawn(
+                                TextBundle::from_section(name.clone(), name_style.clone())
+                                    .with_style(Style {
+                                        flex_grow: 1.,
+                                        ..default()
+                                    }),
+                            );
+
+                            let star_color = if starred {
+                                Color::rgb(0.9, 0.8, 0.2)
+                            } else {
+                                Color::rgba(1., 1., 1., 0.3)
+                            };
+
+                            parent
+                                .spawn((
+                                    NodeBundle {
+                                        style: Style {
+                                            padding: UiRect::horizontal(Val::Px(4.)),
+                                            ..default()
+                                        },
+                                        focus_policy: FocusPolicy::Block,
+                                        ..default()
+                                    },
+                                    Interaction::default(),
+                                    FavouriteButton(name),
+                                ))
+                                .with_children(|parent| {
+                                    parent.spawn(TextBundle::from_section(
+                                        "*",
+                                        TextStyle {
+                                            color: star_color,
+                                            font_size: 18.,
+                                            ..name_style.clone()
+                                        },
+                                    ));
+                                });
+                        });
+                }
+            }
+        });
+}
@@ -0,0 +1,188 @@
+use bevy::prelude::*;
+
+use crate::{
+    controls::input_map::{Action, Actions},
+    simulation::economy::{Budget, CityStats, MonthStats},
+};
+
+/// Panel with the city budget, population and jobs plus graphs of their history, toggled
+/// with `F1`.
+pub struct StatsPanelPlugin;
+
+impl Plugin for StatsPanelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup);
+        app.add_systems(Update, (toggle_panel, update_text, update_graphs));
+    }
+}
+
+#[derive(Component)]
+struct StatsPanel;
+
+#[derive(Component)]
+struct StatsText;
+
+#[derive(Component, Clone, Copy)]
+enum StatsGraph {
+    Funds,
+    Population,
+    Jobs,
+}
+
+impl StatsGraph {
+    const ALL: [Self; 3] = [Self::Funds, Self::Population, Self::Jobs];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Funds => "Funds",
+            Self::Population => "Population",
+            Self::Jobs => "Jobs",
+        }
+    }
+
+    fn value(&self, month: &MonthStats) -> i64 {
+        match self {
+            Self::Funds => month.funds,
+            Self::Population => month.population as i64,
+            Self::Jobs => month.jobs as i64,
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            Self::Funds => Color::rgb(0.9, 0.8, 0.2),
+            Self::Population => Color::rgb(0.2, 0.8, 0.2),
+            Self::Jobs => Color::rgb(0.2, 0.4, 0.9),
+        }
+    }
+}
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("./fonts/Roboto-Regular.ttf");
+
+    let text_style = TextStyle {
+        font: font.clone(),
+        font_size: 16.,
+        color: Color::WHITE,
+    };
+
+    let label_style = TextStyle {
+        font,
+        font_size: 12.,
+        color: Color::GRAY,
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    display: Display::None,
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(10.),
+                    right: Val::Px(10.),
+                    width: Val::Px(220.),
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(8.)),
+                    row_gap: Val::Px(4.),
+                    ..default()
+                },
+                background_color: Color::rgba(0., 0., 0., 0.6).into(),
+                ..default()
+            },
+            Interaction::default(),
+            StatsPanel,
+        ))
+        .with_children(|parent| {
+            parent.spawn((TextBundle::from_section("", text_style), StatsText));
+
+            for graph in StatsGraph::ALL {
+                parent.spawn(TextBundle::from_section(graph.label(), label_style.clone()));
+
+                parent.spawn((
+                    NodeBundle {
+                        style: Style {
+                            width: Val::Percent(100.),
+                            height: Val::Px(40.),
+                            flex_direction: FlexDirection::Row,
+                            align_items: AlignItems::FlexEnd,
+                            column_gap: Val::Px(1.),
+                            ..default()
+                        },
+                        background_color: Color::rgba(1., 1., 1., 0.05).into(),
+                        ..default()
+                    },
+                    graph,
+                ));
+            }
+        });
+}
+
+fn toggle_panel(actions: Res<Actions>, mut panel: Query<&mut Style, With<StatsPanel>>) {
+    if !actions.just_pressed(Action::ToggleStats) {
+        return;
+    }
+
+    let mut style = panel.single_mut();
+
+    style.display = match style.display {
+        Display::None => Display::Flex,
+        _ => Display::None,
+    };
+}
+
+fn update_text(
+    budget: Res<Budget>,
+    stats: Res<CityStats>,
+    mut text: Query<&mut Text, With<StatsText>>,
+) {
+    if !budget.is_changed() && !stats.is_changed() {
+        return;
+    }
+
+    let month = stats.current;
+
+    text.single_mut().sections[0].value = format!(
+        "Funds: {}\nPopulation: {}\nJobs: {}\nLast month: +{} / -{}",
+        budget.funds, month.population, month.jobs, month.income, month.expenses
+    );
+}
+
+/// Rebuilds the bars of every graph out of the month history, negative values are drawn in
+/// red with the height of their absolute value.
+fn update_graphs(
+    mut commands: Commands,
+    stats: Res<CityStats>,
+    graphs: Query<(Entity, &StatsGraph)>,
+) {
+    if !stats.is_changed() {
+        return;
+    }
+
+    for (entity, graph) in graphs.iter() {
+        let values: Vec<i64> = stats.history.iter().map(|m| graph.value(m)).collect();
+        let max = values.iter().map(|v| v.abs()).max().unwrap_or(0).max(1);
+
+        commands
+            .entity(entity)
+            .despawn_descendants()
+            .with_children(|parent| {
+                for value in values {
+                    let color = if value < 0 {
+                        Color::rgb(0.9, 0.2, 0.2)
+                    } else {
+                        graph.color()
+                    };
+
+                    parent.spawn(NodeBundle {
+                        style: Style {
+                            width: Val::Px(7.),
+                            height: Val::Percent(value.abs() as f32 / max as f32 * 100.),
+                            ..default()
+                        },
+                        background_color: color.into(),
+                        ..default()
+                    });
+                }
+            });
+    }
+}
@@ -0,0 +1,231 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::{
+    core_pipeline::clear_color::ClearColorConfig,
+    prelude::*,
+    render::{
+        camera::RenderTarget,
+        render_resource::{
+            Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+        },
+    },
+};
+
+use crate::{
+    controls::model_cursor::ModelCursor,
+    models::{ModelCache, ModelsState},
+    world::batching::ModelMeshes,
+};
+
+/// Renders a picture of every catalog model for the palette, once, after the models loaded.
+///
+/// Each model is spawned far below the map with a camera of its own drawing it into a
+/// texture. Once the cameras had a few frames to render everything is despawned again and
+/// only the textures stay. Models that are reloaded get their picture rendered again.
+pub struct ThumbnailPlugin;
+
+impl Plugin for ThumbnailPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Thumbnails::default());
+        app.add_systems(
+            Update,
+            (reload_thumbnails, render_thumbnails)
+                .chain()
+                .run_if(in_state(ModelsState::Ready)),
+        );
+    }
+}
+
+/// The picture of each model by its path.
+///
+/// `stale` Models that changed since their picture was rendered.
+#[derive(Resource, Default)]
+pub struct Thumbnails {
+    images: HashMap<&'static str, Handle<Image>>,
+    stale: HashSet<&'static str>,
+    studio: Vec<Entity>,
+    frames: u32,
+    done: bool,
+}
+
+impl Thumbnails {
+    /// Width and height of a picture in pixels.
+    pub const SIZE: u32 = 64;
+
+    /// Where the models are spawned, out of sight of the main camera.
+    const STUDIO: Vec3 = Vec3::new(0., -5000., 0.);
+    const SPACING: f32 = 200.;
+
+    /// Frames the studio stays up, the scenes spawn a frame after the cameras and their
+    /// materials take a few more to be ready.
+    const FRAMES: u32 = 10;
+
+    pub fn get(&self, path: &'static str) -> Option<Handle<Image>> {
+        self.images.get(path).cloned()
+    }
+}
+
+fn reload_thumbnails(
+    mut events: EventReader<AssetEvent<Scene>>,
+    cache: Res<ModelCache>,
+    mut thumbnails: ResMut<Thumbnails>,
+) {
+    for event in events.iter() {
+        let AssetEvent::Modified { handle } = event else {
+            continue;
+        };
+
+        let Some(path) = cache.path_of(handle) else {
+            continue;
+        };
+
+        if thumbnails.images.contains_key(path) {
+            thumbnails.stale.insert(path);
+            thumbnails.done = false;
+        }
+    }
+}
+
+fn render_thumbnails(
+    mut commands: Commands,
+    cache: Res<ModelCache>,
+    meshes: Res<Assets<Mesh>>,
+    mut models: ResMut<ModelMeshes>,
+    mut images: ResMut<Assets<Image>>,
+    mut thumbnails: ResMut<Thumbnails>,
+) {
+    if thumbnails.done {
+        return;
+    }
+
+    if !thumbnails.studio.is_empty() {
+        thumbnails.frames += 1;
+
+        if thumbnails.frames < Thumbnails::FRAMES {
+            return;
+        }
+
+        for entity in thumbnails.studio.drain(..) {
+            commands.entity(entity).despawn_recursive();
+        }
+
+        // Models reloaded while the studio was up are left for the next round.
+        thumbnails.done = thumbnails.stale.is_empty();
+        return;
+    }
+
+    // Zones and terrain tools only have the marker, the palette shows a swatch for them.
+    let mut paths: Vec<&'static str> = ModelCursor::all()
+        .iter()
+        .filter(|cursor| !cursor.is(ModelCursor::Zones(0)) && !cursor.is(ModelCursor::Terrain(0)))
+        .map(|cursor| cursor.meta().path)
+        .filter(|path| !thumbnails.images.contains_key(path) || thumbnails.stale.contains(path))
+        .collect();
+
+    paths.sort();
+    paths.dedup();
+
+    // The models are framed by their size, so that has to be known first.
+    let mut ready = true;
+
+    for path in paths.iter() {
+        ready &= models.request(&cache, path);
+    }
+
+    if !ready {
+        return;
+    }
+
+    let size = Extent3d {
+        width: Thumbnails::SIZE,
+        height: Thumbnails::SIZE,
+        depth_or_array_layers: 1,
+    };
+
+    for (i, path) in paths.into_iter().enumerate() {
+        let Some((min, max, _)) = models.bounds(path, &meshes) else {
+            continue;
+        };
+
+        thumbnails.stale.remove(path);
+
+        // Rendered again into the same texture, the palette keeps showing it.
+        let image = match thumbnails.images.get(path) {
+            Some(image) => image.clone(),
+            None => images.add(new_image(size)),
+        };
+
+        let origin = Thumbnails::STUDIO + Vec3::X * i as f32 * Thumbnails::SPACING;
+        let center = origin + (min + max) / 2.;
+        let radius = ((max - min).length() / 2.).max(1.);
+        let eye = center + Vec3::new(1., 0.8, 1.).normalize() * radius * 2.5;
+
+        let subject = commands
+            .spawn(SceneBundle {
+                scene: cache.scene(path),
+                transform: Transform::from_translation(origin),
+                ..default()
+            })
+            .id();
+
+        // Lit on its own, the sun could be down.
+        let light = commands
+            .spawn(PointLightBundle {
+                point_light: PointLight {
+                    intensity: 4000. * radius * radius,
+                    range: radius * 6.,
+                    ..default()
+                },
+                transform: Transform::from_translation(eye + Vec3::Y * radius),
+                ..default()
+            })
+            .id();
+
+        let camera = commands
+            .spawn((
+                Camera3dBundle {
+                    camera: Camera {
+                        order: -1,
+                        target: RenderTarget::Image(image.clone()),
+                        ..default()
+                    },
+                    camera_3d: Camera3d {
+                        clear_color: ClearColorConfig::Custom(Color::NONE),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(eye).looking_at(center, Vec3::Y),
+                    ..default()
+                },
+                UiCameraConfig { show_ui: false },
+            ))
+            .id();
+
+        thumbnails.images.insert(path, image);
+        thumbnails.studio.extend([subject, light, camera]);
+    }
+
+    // Nothing could be measured, there's nothing to wait for either.
+    thumbnails.frames = 0;
+    thumbnails.done = thumbnails.studio.is_empty();
+}
+
+fn new_image(size: Extent3d) -> Image {
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: None,
+            size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Bgra8UnormSrgb,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        },
+        ..default()
+    };
+
+    image.resize(size);
+    image
+}
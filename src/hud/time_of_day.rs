@@ -0,0 +1,144 @@
+use bevy::{prelude::*, ui::RelativeCursorPosition};
+
+use crate::environment::lighting::TimeOfDay;
+
+/// Slider at the bottom of the screen to pick the time of day the lighting shows, the
+/// button next to it goes back to following the simulation clock.
+pub struct TimeOfDayPlugin;
+
+impl Plugin for TimeOfDayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup);
+        app.add_systems(
+            Update,
+            (drag_slider, follow_clock_button, update_slider_handle),
+        );
+    }
+}
+
+#[derive(Component)]
+struct TimeSlider;
+
+#[derive(Component)]
+struct TimeSliderHandle;
+
+#[derive(Component)]
+struct FollowClockButton;
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("./fonts/Roboto-Regular.ttf");
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(10.),
+                left: Val::Percent(50.),
+                margin: UiRect::left(Val::Px(-150.)),
+                width: Val::Px(300.),
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(8.),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    NodeBundle {
+                        style: Style {
+                            flex_grow: 1.,
+                            height: Val::Px(12.),
+                            ..default()
+                        },
+                        background_color: Color::rgba(0., 0., 0., 0.6).into(),
+                        ..default()
+                    },
+                    Interaction::default(),
+                    RelativeCursorPosition::default(),
+                    TimeSlider,
+                ))
+                .with_children(|slider| {
+                    slider.spawn((
+                        NodeBundle {
+                            style: Style {
+                                position_type: PositionType::Absolute,
+                                width: Val::Px(4.),
+                                height: Val::Percent(100.),
+                                ..default()
+                            },
+                            background_color: Color::rgb(1., 0.8, 0.3).into(),
+                            ..default()
+                        },
+                        TimeSliderHandle,
+                    ));
+                });
+
+            parent
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            padding: UiRect::axes(Val::Px(6.), Val::Px(2.)),
+                            ..default()
+                        },
+                        background_color: Color::rgba(0., 0., 0., 0.6).into(),
+                        ..default()
+                    },
+                    FollowClockButton,
+                ))
+                .with_children(|button| {
+                    button.spawn(TextBundle::from_section(
+                        "Live",
+                        TextStyle {
+                            font,
+                            font_size: 14.,
+                            color: Color::WHITE,
+                        },
+                    ));
+                });
+        });
+}
+
+fn drag_slider(
+    slider: Query<(&Interaction, &RelativeCursorPosition), With<TimeSlider>>,
+    mut time_of_day: ResMut<TimeOfDay>,
+) {
+    let (interaction, cursor) = slider.single();
+
+    if *interaction != Interaction::Pressed {
+        return;
+    }
+
+    let Some(normalized) = cursor.normalized else {
+        return;
+    };
+
+    time_of_day.follow_clock = false;
+    time_of_day.hours = normalized.x.clamp(0., 1.) * 24.;
+}
+
+fn follow_clock_button(
+    button: Query<&Interaction, (Changed<Interaction>, With<FollowClockButton>)>,
+    mut time_of_day: ResMut<TimeOfDay>,
+) {
+    for interaction in button.iter() {
+        if *interaction == Interaction::Pressed {
+            time_of_day.follow_clock = true;
+        }
+    }
+}
+
+fn update_slider_handle(
+    time_of_day: Res<TimeOfDay>,
+    mut handle: Query<(&mut Style, &mut BackgroundColor), With<TimeSliderHandle>>,
+) {
+    let (mut style, mut color) = handle.single_mut();
+
+    style.left = Val::Percent(time_of_day.hours / 24. * 100.);
+    color.0 = if time_of_day.follow_clock {
+        Color::rgb(1., 0.8, 0.3)
+    } else {
+        Color::WHITE
+    };
+}
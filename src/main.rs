@@ -1,8 +1,14 @@
-use bevy::{core_pipeline::clear_color::ClearColorConfig, prelude::*};
+use std::time::Duration;
 
+use bevy::{asset::ChangeWatcher, core_pipeline::clear_color::ClearColorConfig, prelude::*};
+
+pub mod bench;
 pub mod controls;
 pub mod data;
+pub mod environment;
+pub mod hud;
 pub mod models;
+pub mod simulation;
 pub mod world;
 
 fn setup(mut commands: Commands) {
@@ -17,26 +23,42 @@ fn setup(mut commands: Commands) {
         ..default()
     });
 
-    commands.spawn(Camera3dBundle {
-        transform: Transform::from_xyz(30., 30., 30.).looking_at(Vec3::ZERO, Vec3::Y),
-        ..default()
-    });
+    commands.spawn((
+        Camera3dBundle {
+            transform: Transform::from_xyz(30., 30., 30.).looking_at(Vec3::ZERO, Vec3::Y),
+            ..default()
+        },
+        controls::movement::MainCamera,
+    ));
 }
 
 fn main() {
     let mut app = App::new();
 
+    // Models are picked up again as they're saved while working on them.
+    let watch_for_changes = if cfg!(debug_assertions) {
+        ChangeWatcher::with_delay(Duration::from_millis(200))
+    } else {
+        None
+    };
+
     app.add_plugins((
-        DefaultPlugins,
+        DefaultPlugins.set(AssetPlugin {
+            watch_for_changes,
+            ..default()
+        }),
+        models::ModelsPlugin,
         controls::ControlPlugin,
         world::WorldPlugin,
+        simulation::SimulationPlugin,
+        environment::EnvironmentPlugin,
+        hud::HudPlugin,
         data::DataPlugin, // bevy_inspector_egui::quick::WorldInspectorPlugin::default(),
     ));
 
-    app.insert_resource(AmbientLight {
-        color: Color::WHITE,
-        brightness: 1.0,
-    });
+    if let Some(bench) = bench::BenchPlugin::from_args() {
+        app.add_plugins(bench);
+    }
 
     app.add_systems(Startup, setup);
 
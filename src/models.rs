@@ -1,84 +1,561 @@
-use bevy::prelude::*;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Mutex, OnceLock, RwLock},
+};
 
-use crate::world::point::PointType;
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadState, LoadedAsset},
+    prelude::*,
+    reflect::{TypePath, TypeUuid},
+    utils::BoxedFuture,
+};
+use serde::Deserialize;
 
-#[derive(Debug, Clone)]
+use crate::{
+    controls::model_cursor::ModelCursor,
+    world::{
+        grid::Grid,
+        point::{Point, PointType},
+    },
+};
+
+/// Loads every model of the catalog once at startup and keeps the handles around, so
+/// placing or spawning a model never waits for it to load.
+///
+/// Models that fail to load show up as a magenta box. In debug builds models and the
+/// [`Catalog`] are reloaded as their files change, everything showing one is spawned again,
+/// and models that failed are tried again until they load.
+pub struct ModelsPlugin;
+
+impl Plugin for ModelsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_state::<ModelsState>();
+        app.add_asset::<Catalog>();
+        app.init_asset_loader::<CatalogLoader>();
+        app.insert_resource(ModelCache::default());
+        app.add_systems(PreStartup, preload_models);
+        app.add_systems(Update, reload_catalog);
+        app.add_systems(Update, check_models.run_if(in_state(ModelsState::Loading)));
+        app.add_systems(Update, (replace_failed, respawn_reloaded));
+        app.add_systems(Update, recover_failed.run_if(|| cfg!(debug_assertions)));
+    }
+}
+
+/// `Loading` until every model of the catalog has loaded or failed to.
+#[derive(States, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ModelsState {
+    #[default]
+    Loading,
+    Ready,
+}
+
+/// The scene of every catalog model by its path.
+///
+/// `failed` Models that couldn't be loaded and the placeholder handed out in place of each,
+/// a scene of its own so it can be told apart once the model loads after all.
+///
+/// `placeholder` What the placeholders are made of.
+///
+/// `catalog` The catalog file, kept loaded so it's reloaded when it changes.
+#[derive(Resource, Default)]
+pub struct ModelCache {
+    scenes: HashMap<&'static str, Handle<Scene>>,
+    failed: HashMap<&'static str, Handle<Scene>>,
+    placeholder: PbrBundle,
+    catalog: Handle<Catalog>,
+}
+
+impl ModelCache {
+    /// Seconds between tries to load the models that failed.
+    const RETRY: f32 = 2.;
+
+    pub fn scene(&self, path: &'static str) -> Handle<Scene> {
+        if let Some(placeholder) = self.failed.get(path) {
+            return placeholder.clone();
+        }
+
+        self.scenes
+            .get(path)
+            .expect("Model is in the catalog!")
+            .clone()
+    }
+
+    /// Starts loading the model of `meta` and its far version, unless they're loaded already.
+    fn load(&mut self, asset_server: &AssetServer, meta: &Meta) {
+        for path in [Some(meta.path), meta.lod].into_iter().flatten() {
+            self.scenes
+                .entry(path)
+                .or_insert_with(|| asset_server.load(path));
+        }
+    }
+
+    /// The catalog path `scene` was loaded from.
+    pub fn path_of(&self, scene: &Handle<Scene>) -> Option<&'static str> {
+        self.scenes
+            .iter()
+            .find(|(_, handle)| *handle == scene)
+            .map(|(path, _)| *path)
+    }
+
+    /// How many models are done loading, failed ones included, out of how many.
+    pub fn progress(&self, asset_server: &AssetServer) -> (usize, usize) {
+        let done = self
+            .scenes
+            .values()
+            .filter(|scene| {
+                matches!(
+                    asset_server.get_load_state(*scene),
+                    LoadState::Loaded | LoadState::Failed
+                )
+            })
+            .count();
+
+        (done, self.scenes.len())
+    }
+}
+
+fn preload_models(
+    asset_server: Res<AssetServer>,
+    grid: Res<Grid>,
+    mut cache: ResMut<ModelCache>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let size = grid.cell_size / 2.;
+
+    cache.placeholder = PbrBundle {
+        mesh: meshes.add(shape::Cube::new(size).into()),
+        material: materials.add(StandardMaterial {
+            base_color: Color::FUCHSIA,
+            unlit: true,
+            ..default()
+        }),
+        transform: Transform::from_xyz(0., size / 2., 0.),
+        ..default()
+    };
+
+    cache.catalog = asset_server.load(Catalog::PATH);
+
+    // Bridges aren't in the palette, but they use the models of the roads they were.
+    for cursor in ModelCursor::all() {
+        cache.load(&asset_server, &cursor.meta());
+    }
+}
+
+/// Puts the catalog file in use once it's loaded and whenever it changes, loading the models
+/// it added and spawning every point again in case its model changed.
+fn reload_catalog(
+    mut events: EventReader<AssetEvent<Catalog>>,
+    catalogs: Res<Assets<Catalog>>,
+    asset_server: Res<AssetServer>,
+    mut cache: ResMut<ModelCache>,
+    mut points: Query<(&mut Point, Option<&mut Handle<Scene>>)>,
+) {
+    for event in events.iter() {
+        let (AssetEvent::Created { handle } | AssetEvent::Modified { handle }) = event else {
+            continue;
+        };
+
+        let Some(catalog) = catalogs.get(handle) else {
+            continue;
+        };
+
+        if *catalog == *Catalog::current().read().unwrap() {
+            continue;
+        }
+
+        info!("Reloaded the catalog");
+        *Catalog::current().write().unwrap() = catalog.clone();
+
+        for cursor in ModelCursor::all() {
+            cache.load(&asset_server, &cursor.meta());
+        }
+
+        // Changing the points also rebuilds the batches and far versions they're in.
+        for (mut point, scene) in points.iter_mut() {
+            point.set_changed();
+
+            if let Some(mut scene) = scene {
+                *scene = cache.scene(point.has.meta().path);
+            }
+        }
+    }
+}
+
+fn check_models(
+    asset_server: Res<AssetServer>,
+    cache: Res<ModelCache>,
+    mut next_state: ResMut<NextState<ModelsState>>,
+) {
+    let (done, total) = cache.progress(&asset_server);
+
+    if done < total {
+        return;
+    }
+
+    info!("Loaded {} models", total);
+    next_state.set(ModelsState::Ready);
+}
+
+/// Swaps models that failed to load for the placeholder, wherever they were spawned.
+fn replace_failed(
+    asset_server: Res<AssetServer>,
+    mut cache: ResMut<ModelCache>,
+    mut scenes: ResMut<Assets<Scene>>,
+    mut spawned: Query<&mut Handle<Scene>>,
+) {
+    let failed: Vec<(&'static str, Handle<Scene>)> = cache
+        .scenes
+        .iter()
+        .filter(|(path, _)| !cache.failed.contains_key(*path))
+        .filter(|(_, scene)| asset_server.get_load_state(*scene) == LoadState::Failed)
+        .map(|(path, scene)| (*path, scene.clone()))
+        .collect();
+
+    for (path, scene) in failed {
+        warn!("Model {} failed to load, showing a placeholder", path);
+
+        let mut world = World::new();
+        world.spawn(cache.placeholder.clone());
+
+        let placeholder = scenes.add(Scene::new(world));
+
+        for mut handle in spawned.iter_mut() {
+            if *handle == scene {
+                *handle = placeholder.clone();
+            }
+        }
+
+        cache.failed.insert(path, placeholder);
+    }
+}
+
+/// Puts models that load after failing in place of their placeholder, and every few seconds
+/// tries the ones still failing again, their files aren't watched until they load once.
+fn recover_failed(
+    time: Res<Time>,
+    mut since_retry: Local<f32>,
+    asset_server: Res<AssetServer>,
+    mut cache: ResMut<ModelCache>,
+    mut scenes: ResMut<Assets<Scene>>,
+    mut spawned: Query<&mut Handle<Scene>>,
+) {
+    if cache.failed.is_empty() {
+        return;
+    }
+
+    let recovered: Vec<&'static str> = cache
+        .failed
+        .keys()
+        .filter(|path| asset_server.get_load_state(&cache.scenes[*path]) == LoadState::Loaded)
+        .copied()
+        .collect();
+
+    for path in recovered {
+        info!("Loaded {} after all", path);
+
+        let placeholder = cache.failed.remove(path).unwrap();
+        let scene = cache.scenes[path].clone();
+
+        for mut handle in spawned.iter_mut() {
+            if *handle == placeholder {
+                *handle = scene.clone();
+            }
+        }
+
+        // Passes as a reload, so whatever was made from the placeholder, like the batches,
+        // is made again from the model.
+        scenes.get_mut(&scene);
+    }
+
+    *since_retry += time.delta_seconds();
+
+    if *since_retry < ModelCache::RETRY {
+        return;
+    }
+
+    *since_retry = 0.;
+
+    for path in cache.failed.keys() {
+        asset_server.reload_asset(*path);
+    }
+}
+
+/// Spawns every scene of a model again once its file changed and it was reloaded.
+fn respawn_reloaded(
+    mut events: EventReader<AssetEvent<Scene>>,
+    cache: Res<ModelCache>,
+    mut spawned: Query<&mut Handle<Scene>>,
+) {
+    for event in events.iter() {
+        let AssetEvent::Modified { handle } = event else {
+            continue;
+        };
+
+        let Some(path) = cache.path_of(handle) else {
+            continue;
+        };
+
+        info!("Reloaded {}", path);
+
+        for mut scene in spawned.iter_mut() {
+            if *scene == *handle {
+                scene.set_changed();
+            }
+        }
+    }
+}
+
+/// Catalog entry of a model
+///
+/// `residents` and `jobs` are what the model adds to the city once placed, `cost` is charged
+/// from the budget when placing it and `upkeep` every month it stays placed.
+///
+/// `lod` Simpler version of the model shown from far away, buildings and nature without
+/// one get a box the size of the model instead, see `world::lod`.
+///
+/// `tags` Other words the palette search finds the model by.
+#[derive(Debug, Clone, PartialEq)]
 pub struct Meta {
     pub path: &'static str,
+    pub lod: Option<&'static str>,
+    pub tags: &'static [&'static str],
+    pub residents: u32,
+    pub jobs: u32,
+    pub cost: i64,
+    pub upkeep: i64,
 }
 
 impl Meta {
     pub const fn new(path: &'static str) -> Self {
-        Self { path }
+        Self {
+            path,
+            lod: None,
+            tags: &[],
+            residents: 0,
+            jobs: 0,
+            cost: 0,
+            upkeep: 0,
+        }
+    }
+
+    /// The file name of the model made readable, `road_corner.glb` is `Road corner`.
+    pub fn name(&self) -> String {
+        let file = self.path.rsplit('/').next().unwrap_or(self.path);
+        let stem = file.split('.').next().unwrap_or(file).replace('_', " ");
+        let mut chars = stem.chars();
+
+        match chars.next() {
+            Some(first) => first.to_uppercase().chain(chars).collect(),
+            None => stem,
+        }
+    }
+}
+
+/// The [`Meta`] of every model, read from [`Catalog::PATH`] under the assets.
+///
+/// Until the file is loaded the copy built into the game is used, and in debug builds it's
+/// read again whenever it changes. A catalog that's missing any model is refused.
+///
+/// `bridge` What roads cost instead when they're bridges, they keep the model of the road.
+#[derive(TypeUuid, TypePath, Debug, Clone, PartialEq)]
+#[uuid = "8b0f3c52-6d1e-4a7f-b2c9-5e8a1d4f7c30"]
+pub struct Catalog {
+    models: HashMap<PointType, Meta>,
+    bridge: BridgePrice,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+struct BridgePrice {
+    cost: i64,
+    upkeep: i64,
+}
+
+/// A [`Meta`] as it's written in the catalog file.
+#[derive(Deserialize)]
+struct CatalogEntry {
+    path: String,
+    #[serde(default)]
+    lod: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    residents: u32,
+    #[serde(default)]
+    jobs: u32,
+    #[serde(default)]
+    cost: i64,
+    #[serde(default)]
+    upkeep: i64,
+}
+
+#[derive(Deserialize)]
+struct CatalogFile {
+    models: HashMap<PointType, CatalogEntry>,
+    bridge: BridgePrice,
+}
+
+static CATALOG: OnceLock<RwLock<Catalog>> = OnceLock::new();
+
+impl Catalog {
+    pub const PATH: &'static str = "./models.catalog.json";
+
+    const BUILT_IN: &'static str = include_str!("../assets/models.catalog.json");
+
+    fn current() -> &'static RwLock<Catalog> {
+        CATALOG.get_or_init(|| {
+            RwLock::new(Self::parse(Self::BUILT_IN.as_bytes()).expect("Built in catalog is valid!"))
+        })
+    }
+
+    /// The entry of `has` in the catalog in use.
+    pub fn meta(has: &PointType) -> Meta {
+        Self::current().read().unwrap().get(has)
+    }
+
+    fn get(&self, has: &PointType) -> Meta {
+        match has {
+            PointType::Bridge(road) => Meta {
+                cost: self.bridge.cost,
+                upkeep: self.bridge.upkeep,
+                ..self.get(road)
+            },
+            _ => self
+                .models
+                .get(has)
+                .cloned()
+                .unwrap_or_else(|| panic!("{:?} isn't in the catalog", has)),
+        }
+    }
+
+    fn parse(bytes: &[u8]) -> Result<Self, bevy::asset::Error> {
+        let file: CatalogFile = serde_json::from_slice(bytes)?;
+
+        let floors = FloorModel::INDICES.iter().cloned().map(PointType::from);
+        let buildings = BuildingModel::INDICES.iter().cloned().map(PointType::from);
+        let nature = NatureModel::INDICES.iter().cloned().map(PointType::from);
+
+        let missing: Vec<PointType> = floors
+            .chain(buildings)
+            .chain(nature)
+            .filter(|has| !file.models.contains_key(has))
+            .collect();
+
+        if !missing.is_empty() {
+            return Err(bevy::asset::Error::msg(format!(
+                "The catalog is missing {:?}",
+                missing
+            )));
+        }
+
+        let models = file
+            .models
+            .into_iter()
+            .map(|(has, entry)| {
+                let meta = Meta {
+                    path: intern(&entry.path),
+                    lod: entry.lod.as_deref().map(intern),
+                    tags: intern_tags(&entry.tags),
+                    residents: entry.residents,
+                    jobs: entry.jobs,
+                    cost: entry.cost,
+                    upkeep: entry.upkeep,
+                };
+
+                (has, meta)
+            })
+            .collect();
+
+        Ok(Self {
+            models,
+            bridge: file.bridge,
+        })
+    }
+}
+
+/// The strings of the catalog last as long as the game, when it's read again the ones that
+/// didn't change are reused instead of kept a second time.
+fn intern(string: &str) -> &'static str {
+    static STRINGS: OnceLock<Mutex<HashSet<&'static str>>> = OnceLock::new();
+
+    let mut strings = STRINGS.get_or_init(default).lock().unwrap();
+
+    if let Some(interned) = strings.get(string) {
+        return interned;
+    }
+
+    let interned: &'static str = Box::leak(string.to_owned().into_boxed_str());
+    strings.insert(interned);
+    interned
+}
+
+fn intern_tags(tags: &[String]) -> &'static [&'static str] {
+    static TAGS: OnceLock<Mutex<HashSet<&'static [&'static str]>>> = OnceLock::new();
+
+    let tags: Vec<&'static str> = tags.iter().map(|tag| intern(tag)).collect();
+    let mut interned_tags = TAGS.get_or_init(default).lock().unwrap();
+
+    if let Some(interned) = interned_tags.get(tags.as_slice()) {
+        return interned;
+    }
+
+    let interned: &'static [&'static str] = Box::leak(tags.into_boxed_slice());
+    interned_tags.insert(interned);
+    interned
+}
+
+#[derive(Default)]
+struct CatalogLoader;
+
+impl AssetLoader for CatalogLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            load_context.set_default_asset(LoadedAsset::new(Catalog::parse(bytes)?));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["catalog.json"]
     }
 }
 
 #[derive(Debug, Clone, Resource)]
 pub enum FloorModel {
-    Grass(Meta),
-    Concrete(Meta),
-    RoadStraight(Meta),
-    RoadStraightWalkable(Meta),
-    RoadEnd(Meta),
-    RoadStraightSideOpen(Meta),
-
-    RoadCornerWalkable(Meta),
-    RoadCorner(Meta),
-    RoadIntersection(Meta),
-    RoadIntersectionWalkable(Meta),
+    Grass,
+    Concrete,
+    RoadStraight,
+    RoadStraightWalkable,
+    RoadEnd,
+    RoadStraightSideOpen,
+
+    RoadCornerWalkable,
+    RoadCorner,
+    RoadIntersection,
+    RoadIntersectionWalkable,
     None,
 }
 
 impl FloorModel {
-    const CONCRETE: Self =
-        Self::Concrete(Meta::new("./models/roads/road_prop_tile_dark.glb#Scene0"));
-    const GRASS: Self = Self::Grass(Meta::new("./models/grass_flat.glb#Scene0"));
-
-    const ROAD_STRAIGHT: Self =
-        Self::RoadStraight(Meta::new("./models/roads/road_straight.glb#Scene0"));
-    const ROAD_STRAIGHT_WALKABLE: Self = Self::RoadStraightWalkable(Meta::new(
-        "./models/roads/road_straight_walkable.glb#Scene0",
-    ));
-    const ROAD_STRAIGHT_SIDE_OPEN: Self = Self::RoadStraightSideOpen(Meta::new(
-        "./models/roads/road_straight_side_open.glb#Scene0",
-    ));
-    const ROAD_END: Self = Self::RoadEnd(Meta::new("./models/roads/road_end.glb#Scene0"));
-    const ROAD_CORNER: Self = Self::RoadCorner(Meta::new("./models/roads/road_corner.glb#Scene0"));
-    const ROAD_CORNER_WALKABLE: Self =
-        Self::RoadCornerWalkable(Meta::new("./models/roads/road_corner_walkable.glb#Scene0"));
-    const ROAD_INTERSECTION: Self =
-        Self::RoadIntersection(Meta::new("./models/roads/road_intersection.glb#Scene0"));
-    const ROAD_INTERSECTION_WALKABLE: Self = Self::RoadIntersectionWalkable(Meta::new(
-        "./models/roads/road_intersection_walkable.glb#Scene0",
-    ));
-
     const INDICES: [Self; 10] = [
-        Self::CONCRETE,
-        Self::GRASS,
-        Self::ROAD_STRAIGHT,
-        Self::ROAD_STRAIGHT_WALKABLE,
-        Self::ROAD_STRAIGHT_SIDE_OPEN,
-        Self::ROAD_END,
-        Self::ROAD_CORNER,
-        Self::ROAD_CORNER_WALKABLE,
-        Self::ROAD_INTERSECTION,
-        Self::ROAD_INTERSECTION_WALKABLE,
+        Self::Concrete,
+        Self::Grass,
+        Self::RoadStraight,
+        Self::RoadStraightWalkable,
+        Self::RoadStraightSideOpen,
+        Self::RoadEnd,
+        Self::RoadCorner,
+        Self::RoadCornerWalkable,
+        Self::RoadIntersection,
+        Self::RoadIntersectionWalkable,
     ];
 
-    pub fn get_meta(&self) -> &Meta {
-        match self {
-            Self::Grass(meta) => meta,
-            Self::Concrete(meta) => meta,
-            Self::RoadStraight(meta) => meta,
-            Self::RoadCornerWalkable(meta) => meta,
-            Self::RoadStraightSideOpen(meta) => meta,
-            Self::RoadCorner(meta) => meta,
-            Self::RoadEnd(meta) => meta,
-            Self::RoadIntersection(meta) => meta,
-            Self::RoadIntersectionWalkable(meta) => meta,
-            Self::RoadStraightWalkable(meta) => meta,
-            Self::None => panic!("None doesn't have meta!"),
-        }
+    pub fn get_meta(&self) -> Meta {
+        PointType::from(self.clone()).meta()
     }
 
     pub fn len() -> usize {
@@ -92,21 +569,37 @@ impl FloorModel {
 
 #[derive(Clone)]
 pub enum BuildingModel {
-    Blgd01_01(Meta),
-    Blgd02_01(Meta),
+    Blgd01_01,
+    Blgd02_01,
 }
 
 impl BuildingModel {
-    const BLGD01_01: Self = Self::Blgd01_01(Meta::new("./models/bldg/bldg_01_01.glb#Scene0"));
-    const BLGD02_01: Self = Self::Blgd02_01(Meta::new("./models/bldg/bldg_02_01.glb#Scene0"));
+    const INDICES: [Self; 2] = [Self::Blgd01_01, Self::Blgd02_01];
 
-    const INDICES: [Self; 2] = [Self::BLGD01_01, Self::BLGD02_01];
+    pub fn get_meta(&self) -> Meta {
+        PointType::from(self.clone()).meta()
+    }
 
-    pub fn get_meta(&self) -> &Meta {
-        match self {
-            Self::Blgd01_01(meta) => meta,
-            Self::Blgd02_01(meta) => meta,
-        }
+    pub fn len() -> usize {
+        Self::INDICES.len()
+    }
+
+    pub fn index(index: usize) -> Self {
+        Self::INDICES.get(index).unwrap().clone()
+    }
+}
+
+#[derive(Clone)]
+pub enum NatureModel {
+    Tree01,
+    Tree02,
+}
+
+impl NatureModel {
+    const INDICES: [Self; 2] = [Self::Tree01, Self::Tree02];
+
+    pub fn get_meta(&self) -> Meta {
+        PointType::from(self.clone()).meta()
     }
 
     pub fn len() -> usize {
@@ -122,16 +615,16 @@ impl TryFrom<PointType> for FloorModel {
     type Error = &'static str;
     fn try_from(value: PointType) -> Result<Self, Self::Error> {
         match value {
-            PointType::Concrete => Ok(Self::CONCRETE),
-            PointType::Grass => Ok(Self::GRASS),
-            PointType::RoadStraight => Ok(Self::ROAD_STRAIGHT),
-            PointType::RoadStraightWalkable => Ok(Self::ROAD_STRAIGHT_WALKABLE),
-            PointType::RoadStraightSideOpen => Ok(Self::ROAD_STRAIGHT_SIDE_OPEN),
-            PointType::RoadEnd => Ok(Self::ROAD_END),
-            PointType::RoadCorner => Ok(Self::ROAD_CORNER),
-            PointType::RoadCornerWalkable => Ok(Self::ROAD_CORNER_WALKABLE),
-            PointType::RoadIntersection => Ok(Self::ROAD_INTERSECTION),
-            PointType::RoadIntersectionWalkable => Ok(Self::ROAD_INTERSECTION_WALKABLE),
+            PointType::Concrete => Ok(Self::Concrete),
+            PointType::Grass => Ok(Self::Grass),
+            PointType::RoadStraight => Ok(Self::RoadStraight),
+            PointType::RoadStraightWalkable => Ok(Self::RoadStraightWalkable),
+            PointType::RoadStraightSideOpen => Ok(Self::RoadStraightSideOpen),
+            PointType::RoadEnd => Ok(Self::RoadEnd),
+            PointType::RoadCorner => Ok(Self::RoadCorner),
+            PointType::RoadCornerWalkable => Ok(Self::RoadCornerWalkable),
+            PointType::RoadIntersection => Ok(Self::RoadIntersection),
+            PointType::RoadIntersectionWalkable => Ok(Self::RoadIntersectionWalkable),
             _ => Err("Not found"),
         }
     }
@@ -142,9 +635,52 @@ impl TryFrom<PointType> for BuildingModel {
 
     fn try_from(value: PointType) -> Result<Self, Self::Error> {
         match value {
-            PointType::Blgd01_01 => Ok(Self::BLGD01_01),
-            PointType::Blgd02_01 => Ok(Self::BLGD02_01),
+            PointType::Blgd01_01 => Ok(Self::Blgd01_01),
+            PointType::Blgd02_01 => Ok(Self::Blgd02_01),
             _ => Err("Not found"),
         }
     }
 }
+
+impl TryFrom<PointType> for NatureModel {
+    type Error = &'static str;
+
+    fn try_from(value: PointType) -> Result<Self, Self::Error> {
+        match value {
+            PointType::Tree01 => Ok(Self::Tree01),
+            PointType::Tree02 => Ok(Self::Tree02),
+            _ => Err("Not found"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn built_in_catalog_has_every_model() {
+        let catalog = Catalog::parse(Catalog::BUILT_IN.as_bytes()).unwrap();
+
+        let tree = catalog.get(&PointType::Tree02);
+
+        assert_eq!(tree.path, "./models/nature/tree_02.glb#Scene0");
+    }
+
+    #[test]
+    fn bridges_keep_the_model_of_their_road() {
+        let catalog = Catalog::parse(Catalog::BUILT_IN.as_bytes()).unwrap();
+        let road = catalog.get(&PointType::RoadCorner);
+        let bridge = catalog.get(&PointType::Bridge(Box::new(PointType::RoadCorner)));
+
+        assert_eq!(bridge.path, road.path);
+        assert_eq!(bridge.cost, catalog.bridge.cost);
+    }
+
+    #[test]
+    fn catalogs_missing_a_model_are_refused() {
+        let file = r#"{ "bridge": { "cost": 1, "upkeep": 1 }, "models": {} }"#;
+
+        assert!(Catalog::parse(file.as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,152 @@
+use bevy::{ecs::schedule::ScheduleLabel, prelude::*};
+
+use crate::controls::input_map::{Action, Actions};
+
+/// Time base of the simulation.
+///
+/// Simulation systems go in the [`SimUpdate`] schedule which runs on the fixed timestep once
+/// per step of the clock, so pausing freezes them while editing keeps running in `Update`.
+pub struct ClockPlugin;
+
+impl Plugin for ClockPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(FixedTime::new_from_secs(SimClock::STEP_SECONDS));
+        app.insert_resource(SimClock::default());
+        app.init_schedule(SimUpdate);
+        app.add_systems(FixedUpdate, run_simulation);
+        app.add_systems(Update, control_clock);
+    }
+}
+
+/// Runs once for every step the clock advances.
+#[derive(ScheduleLabel, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SimUpdate;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SimSpeed {
+    #[default]
+    Normal,
+    Fast,
+    Fastest,
+}
+
+impl SimSpeed {
+    /// Steps the clock advances every fixed timestep.
+    pub fn steps(&self) -> u32 {
+        match self {
+            Self::Normal => 1,
+            Self::Fast => 2,
+            Self::Fastest => 4,
+        }
+    }
+
+    pub fn faster(&self) -> Self {
+        match self {
+            Self::Normal => Self::Fast,
+            _ => Self::Fastest,
+        }
+    }
+
+    pub fn slower(&self) -> Self {
+        match self {
+            Self::Fastest => Self::Fast,
+            _ => Self::Normal,
+        }
+    }
+}
+
+/// In-game time, counted in minutes since midnight of the first day of the first year.
+///
+/// `paused` Stops the clock, `step_requested` still lets a single step through.
+#[derive(Resource, Debug, Default)]
+pub struct SimClock {
+    pub minutes: u64,
+    pub paused: bool,
+    pub speed: SimSpeed,
+    pub step_requested: bool,
+    last_minutes: u64,
+}
+
+impl SimClock {
+    /// Real seconds between fixed timesteps.
+    pub const STEP_SECONDS: f32 = 0.05;
+    /// In-game minutes that pass every step.
+    pub const STEP_MINUTES: u64 = 10;
+
+    pub const HOUR: u64 = 60;
+    pub const DAY: u64 = Self::HOUR * 24;
+    pub const MONTH: u64 = Self::DAY * 30;
+    pub const YEAR: u64 = Self::MONTH * 12;
+
+    /// Whether the last step crossed a multiple of `period` minutes, used by systems that
+    /// only run every hour, day or month.
+    pub fn every(&self, period: u64) -> bool {
+        self.minutes / period != self.last_minutes / period
+    }
+
+    /// Hours into the current day, with the minutes as the fractional part.
+    pub fn time_of_day(&self) -> f32 {
+        (self.minutes % Self::DAY) as f32 / Self::HOUR as f32
+    }
+
+    /// Year, month and day, all starting from `1`.
+    pub fn date(&self) -> (u64, u64, u64) {
+        (
+            self.minutes / Self::YEAR + 1,
+            self.minutes % Self::YEAR / Self::MONTH + 1,
+            self.minutes % Self::MONTH / Self::DAY + 1,
+        )
+    }
+
+    /// Jumps to `minutes` without any of the periods counting as passed.
+    pub fn set_minutes(&mut self, minutes: u64) {
+        self.minutes = minutes;
+        self.last_minutes = minutes;
+    }
+
+    fn advance(&mut self) {
+        self.last_minutes = self.minutes;
+        self.minutes += Self::STEP_MINUTES;
+    }
+
+    fn steps_this_tick(&mut self) -> u32 {
+        if !self.paused {
+            return self.speed.steps();
+        }
+
+        if self.step_requested {
+            self.step_requested = false;
+            return 1;
+        }
+
+        0
+    }
+}
+
+fn run_simulation(world: &mut World) {
+    let steps = world.resource_mut::<SimClock>().steps_this_tick();
+
+    for _ in 0..steps {
+        world.resource_mut::<SimClock>().advance();
+        world.run_schedule(SimUpdate);
+    }
+}
+
+/// `Space` pauses, `+` and `-` change the speed and `.` runs a single step while paused.
+fn control_clock(actions: Res<Actions>, mut clock: ResMut<SimClock>) {
+    if actions.just_pressed(Action::Pause) {
+        clock.paused = !clock.paused;
+    }
+
+    if actions.just_pressed(Action::SpeedUp) {
+        clock.speed = clock.speed.faster();
+    }
+
+    if actions.just_pressed(Action::SlowDown) {
+        clock.speed = clock.speed.slower();
+    }
+
+    if actions.just_pressed(Action::Step) && clock.paused {
+        clock.step_requested = true;
+    }
+}
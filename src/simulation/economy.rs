@@ -0,0 +1,122 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::world::World;
+
+use super::clock::{SimClock, SimUpdate};
+
+/// Keeps the city budget, charging upkeep and collecting taxes every month.
+pub struct EconomyPlugin;
+
+impl Plugin for EconomyPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Budget::default());
+        app.insert_resource(EconomyConfig::default());
+        app.insert_resource(CityStats::default());
+        app.add_systems(SimUpdate, monthly_tick);
+    }
+}
+
+/// Money the city has to spend on roads and buildings.
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+pub struct Budget {
+    pub funds: i64,
+}
+
+impl Default for Budget {
+    fn default() -> Self {
+        Self { funds: 10_000 }
+    }
+}
+
+impl Budget {
+    /// Takes `cost` out of the funds, returns `false` and leaves the funds as they were when
+    /// there isn't enough money.
+    pub fn spend(&mut self, cost: i64) -> bool {
+        if cost > self.funds {
+            return false;
+        }
+
+        self.funds -= cost;
+
+        true
+    }
+}
+
+/// `tax_per_resident` and `tax_per_job` Monthly income for each resident and job in the city.
+#[derive(Resource)]
+pub struct EconomyConfig {
+    pub tax_per_resident: i64,
+    pub tax_per_job: i64,
+}
+
+impl Default for EconomyConfig {
+    fn default() -> Self {
+        Self {
+            tax_per_resident: 2,
+            tax_per_job: 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MonthStats {
+    pub funds: i64,
+    pub population: u32,
+    pub jobs: u32,
+    pub income: i64,
+    pub expenses: i64,
+}
+
+/// Stats of the last month and the ones before it, oldest first.
+#[derive(Resource, Debug, Default)]
+pub struct CityStats {
+    pub current: MonthStats,
+    pub history: VecDeque<MonthStats>,
+}
+
+impl CityStats {
+    /// Months kept around for the graphs.
+    pub const HISTORY_LEN: usize = 24;
+
+    fn push(&mut self, stats: MonthStats) {
+        self.current = stats;
+        self.history.push_back(stats);
+
+        if self.history.len() > Self::HISTORY_LEN {
+            self.history.pop_front();
+        }
+    }
+}
+
+fn monthly_tick(
+    clock: Res<SimClock>,
+    config: Res<EconomyConfig>,
+    mut budget: ResMut<Budget>,
+    mut stats: ResMut<CityStats>,
+    world: Res<World>,
+) {
+    if !clock.every(SimClock::MONTH) {
+        return;
+    }
+
+    let mut month = MonthStats::default();
+
+    for point in world.points() {
+        let meta = point.has.meta();
+
+        month.population += meta.residents;
+        month.jobs += meta.jobs;
+        month.expenses += meta.upkeep;
+    }
+
+    month.income =
+        month.population as i64 * config.tax_per_resident + month.jobs as i64 * config.tax_per_job;
+
+    budget.funds += month.income - month.expenses;
+    month.funds = budget.funds;
+
+    stats.push(month);
+}
@@ -0,0 +1,199 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+
+use crate::{
+    controls::place_model::Orientation,
+    environment::weather::TrafficSpeed,
+    models::ModelCache,
+    world::{
+        batching::TileRendering,
+        grid::Grid,
+        point::{Point, Position},
+        roads::RoadNetwork,
+        spawn_point, World,
+    },
+};
+
+use super::{
+    clock::{SimClock, SimUpdate},
+    zoning::Zones,
+};
+
+/// Grows buildings on zoned cells that have access to a connected road.
+pub struct GrowthPlugin;
+
+impl Plugin for GrowthPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(GrowthConfig::default());
+        app.insert_resource(GrowthProgress::default());
+        app.add_systems(SimUpdate, grow);
+    }
+}
+
+/// `interval` In-game minutes between evaluations of the growth.
+///
+/// `base_rate` Progress a cell with average access and neighbours makes each evaluation, a
+/// building is placed once the progress of a cell reaches `1`.
+#[derive(Resource)]
+pub struct GrowthConfig {
+    pub interval: u64,
+    pub base_rate: f32,
+}
+
+impl Default for GrowthConfig {
+    fn default() -> Self {
+        Self {
+            interval: SimClock::HOUR * 4,
+            base_rate: 0.1,
+        }
+    }
+}
+
+/// How close each zoned cell is to getting a building.
+#[derive(Resource, Default, Debug)]
+pub struct GrowthProgress {
+    pub cells: HashMap<Position, f32>,
+}
+
+/// Rate multiplier from the roads around `pos`, `0` when there's no connected road next to
+/// it. More sides facing a road and bigger networks both help, up to a multiplier of `2`.
+fn road_access(pos: &Position, roads: &RoadNetwork) -> f32 {
+    let mut sides = 0.;
+    let mut largest_network = 0;
+
+    for next in pos.neighbours() {
+        if roads.is_connected(&next) {
+            sides += 1.;
+            largest_network = largest_network.max(roads.network_size(&next));
+        }
+    }
+
+    if sides == 0. {
+        return 0.;
+    }
+
+    let sides_factor: f32 = sides * 0.5;
+    let network_factor = (largest_network as f32 / 20.).min(1.);
+
+    sides_factor.min(1.) + network_factor
+}
+
+fn grow(
+    mut commands: Commands,
+    clock: Res<SimClock>,
+    cache: Res<ModelCache>,
+    grid: Res<Grid>,
+    rendering: Res<TileRendering>,
+    config: Res<GrowthConfig>,
+    traffic: Res<TrafficSpeed>,
+    roads: Res<RoadNetwork>,
+    mut progress: ResMut<GrowthProgress>,
+    zones: Res<Zones>,
+    world: Res<World>,
+) {
+    if !clock.every(config.interval) {
+        return;
+    }
+
+    let built: HashSet<Position> = world
+        .points()
+        .filter(|point| point.has.is_building() || point.has.is_nature())
+        .map(|point| point.position)
+        .collect();
+
+    progress
+        .cells
+        .retain(|pos, _| zones.get(pos).is_some() && !built.contains(pos));
+
+    for (pos, zone) in zones.cells.iter() {
+        if built.contains(pos) {
+            continue;
+        }
+
+        let access = road_access(pos, &roads);
+
+        if access == 0. {
+            continue;
+        }
+
+        let mut neighbours = 1.;
+
+        for next in pos.surrounding() {
+            if let Some(other) = zones.get(&next) {
+                neighbours += zone.affinity(other);
+            }
+        }
+
+        // Slow traffic in rain or snow makes the roads worth less for a while.
+        let rate = config.base_rate * access * traffic.0 * neighbours.max(0.1);
+        let cell_progress = progress.cells.entry(*pos).or_insert(0.);
+
+        *cell_progress += rate;
+
+        if *cell_progress < 1. {
+            continue;
+        }
+
+        progress.cells.remove(pos);
+
+        let buildings = zone.buildings();
+        let building = buildings
+            [(pos.x.unsigned_abs() + pos.y.unsigned_abs()) as usize % buildings.len()]
+        .clone();
+
+        let side = pos
+            .neighbours()
+            .iter()
+            .position(|next| roads.is_connected(next))
+            .unwrap_or(0);
+
+        spawn_point(
+            &mut commands,
+            &cache,
+            &grid,
+            &rendering,
+            Point::new(building, *pos, facing(side)),
+        );
+    }
+}
+
+/// The orientation that turns a model towards `side`, in the order of
+/// [`Position::neighbours`].
+///
+/// Models face `-Z` and turning them by a positive angle turns them to `-X`, so the `East`
+/// orientation faces the neighbour to the west and the other way around.
+fn facing(side: usize) -> Orientation {
+    const FACING: [usize; 4] = [
+        0, // North
+        3, // West
+        2, // South
+        1, // East
+    ];
+
+    Orientation::index(FACING[side])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buildings_face_the_neighbour_on_their_side() {
+        let pos = Position::new(3, -2);
+
+        for (side, next) in pos.neighbours().iter().enumerate() {
+            let orientation = facing(side);
+            let turned = Quat::from_rotation_y(orientation.rotation()) * Vec3::NEG_Z;
+            let step = Vec3::new((next.x - pos.x) as f32, 0., (next.y - pos.y) as f32);
+
+            assert!(
+                turned.abs_diff_eq(step, 1e-5),
+                "{} faces {:?} but its neighbour is at {:?}",
+                orientation.name(),
+                turned,
+                step
+            );
+        }
+    }
+}
@@ -0,0 +1,20 @@
+use bevy::prelude::*;
+
+pub mod clock;
+pub mod economy;
+pub mod growth;
+pub mod zoning;
+
+/// Everything that turns the builder into a (light) city simulation.
+pub struct SimulationPlugin;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            clock::ClockPlugin,
+            zoning::ZoningPlugin,
+            growth::GrowthPlugin,
+            economy::EconomyPlugin,
+        ));
+    }
+}
@@ -0,0 +1,185 @@
+use std::{collections::HashMap, f32::consts::FRAC_PI_2};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    controls::{
+        input_map::{Action, Actions},
+        model_cursor::ModelCursor,
+        mouse_projection::MouseProjection,
+        walk::CameraView,
+    },
+    hud::PointerOverUi,
+    models::Meta,
+    world::{
+        grid::Grid,
+        point::{Point, PointType, Position},
+        terrain::Terrain,
+    },
+};
+
+/// Lets zones be painted on grass cells, those zones are later filled with buildings by the
+/// growth simulation.
+pub struct ZoningPlugin;
+
+impl Plugin for ZoningPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Zones::default());
+        app.add_systems(
+            Update,
+            (paint_zone.run_if(in_state(CameraView::Orbit)), draw_zones),
+        );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Zone {
+    Residential,
+    Commercial,
+    Industrial,
+}
+
+impl Zone {
+    const INDICES: [Self; 3] = [Self::Residential, Self::Commercial, Self::Industrial];
+
+    /// Zones don't have a model of their own, the cursor shows the plain marker instead.
+    const MARKER: Meta = Meta::new("./models/roads/road_prop_concrete.glb#Scene0");
+
+    pub fn len() -> usize {
+        Self::INDICES.len()
+    }
+
+    pub fn index(index: usize) -> Self {
+        *Self::INDICES.get(index).unwrap()
+    }
+
+    pub fn meta(&self) -> Meta {
+        Self::MARKER
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            Self::Residential => Color::rgb(0.2, 0.8, 0.2),
+            Self::Commercial => Color::rgb(0.2, 0.4, 0.9),
+            Self::Industrial => Color::rgb(0.9, 0.7, 0.1),
+        }
+    }
+
+    /// Buildings that can grow on this zone.
+    pub fn buildings(&self) -> &'static [PointType] {
+        match self {
+            Self::Residential => &[PointType::Blgd01_01],
+            Self::Commercial => &[PointType::Blgd02_01],
+            Self::Industrial => &[PointType::Blgd02_01, PointType::Blgd01_01],
+        }
+    }
+
+    /// How much having `other` next door speeds up (or slows down) growth on this zone.
+    pub fn affinity(&self, other: Zone) -> f32 {
+        match (self, other) {
+            (Self::Residential, Self::Residential) => 0.1,
+            (Self::Residential, Self::Commercial) => 0.25,
+            (Self::Residential, Self::Industrial) => -0.25,
+            (Self::Commercial, Self::Residential) => 0.25,
+            (Self::Commercial, Self::Commercial) => -0.1,
+            (Self::Commercial, Self::Industrial) => 0.05,
+            (Self::Industrial, Self::Residential) => -0.1,
+            (Self::Industrial, Self::Commercial) => 0.1,
+            (Self::Industrial, Self::Industrial) => 0.15,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZonedCell {
+    pub position: Position,
+    pub zone: Zone,
+}
+
+/// The zone painted on each cell, cells without zone aren't in the map.
+#[derive(Resource, Default, Debug)]
+pub struct Zones {
+    pub cells: HashMap<Position, Zone>,
+}
+
+impl Zones {
+    pub fn get(&self, pos: &Position) -> Option<Zone> {
+        self.cells.get(pos).copied()
+    }
+
+    pub fn to_cells(&self) -> Vec<ZonedCell> {
+        self.cells
+            .iter()
+            .map(|(position, zone)| ZonedCell {
+                position: *position,
+                zone: *zone,
+            })
+            .collect()
+    }
+
+    pub fn set_cells(&mut self, cells: &[ZonedCell]) {
+        self.cells = cells
+            .iter()
+            .map(|cell| (cell.position, cell.zone))
+            .collect();
+    }
+}
+
+fn paint_zone(
+    cursor: Res<ModelCursor>,
+    mouse_projection: Res<MouseProjection>,
+    actions: Res<Actions>,
+    points: Query<&Point>,
+    mut zones: ResMut<Zones>,
+    pointer_over_ui: Res<PointerOverUi>,
+    grid: Res<Grid>,
+) {
+    let ModelCursor::Zones(index) = *cursor else {
+        return;
+    };
+
+    if pointer_over_ui.0 || !mouse_projection.hit || !grid.contains(&mouse_projection.normal) {
+        return;
+    }
+
+    let cell = mouse_projection.normal;
+
+    if actions.pressed(Action::Remove) {
+        zones.cells.remove(&cell);
+        return;
+    }
+
+    if !actions.pressed(Action::Place) {
+        return;
+    }
+
+    let mut is_grass = false;
+
+    for point in points.iter().filter(|point| point.position == cell) {
+        if point.has.is_building() || point.has.is_nature() {
+            return;
+        }
+
+        if point.has == PointType::Grass {
+            is_grass = true;
+        }
+    }
+
+    if is_grass {
+        zones.cells.insert(cell, Zone::index(index));
+    }
+}
+
+fn draw_zones(zones: Res<Zones>, grid: Res<Grid>, terrain: Res<Terrain>, mut gizmos: Gizmos) {
+    let rotation = Quat::from_rotation_x(FRAC_PI_2);
+
+    for (pos, zone) in zones.cells.iter() {
+        gizmos.rect(
+            terrain.cell_center(&grid, pos) + Vec3::Y * 0.1,
+            rotation,
+            Vec2::splat(grid.cell_size * 0.9),
+            zone.color(),
+        );
+    }
+}
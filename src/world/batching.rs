@@ -0,0 +1,504 @@
+use std::collections::HashMap;
+
+use bevy::{
+    prelude::*,
+    render::{
+        mesh::{Indices, VertexAttributeValues},
+        primitives::Aabb,
+        render_resource::PrimitiveTopology,
+    },
+};
+
+use crate::models::ModelCache;
+
+use super::{
+    chunks::{sync_world, LoadedChunks, StreamConfig},
+    grid::Grid,
+    point::{Layer, Point, PointType, Position},
+    terrain::{Elevation, Terrain},
+    World,
+};
+
+/// Draws the floor tiles of each spawned chunk as a few merged meshes instead of a scene
+/// per tile.
+///
+/// A map is mostly the same handful of floor models over and over, so they're merged per
+/// chunk, model and material. Their [`Point`] entities are still spawned, only without a
+/// scene, so everything else can find them. Buildings and nature keep their own scenes.
+pub struct BatchingPlugin;
+
+impl Plugin for BatchingPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(TileRendering::default());
+        app.insert_resource(ModelMeshes::default());
+        app.insert_resource(Batches::default());
+        app.add_systems(Update, (reload_models, resolve_models).chain());
+        app.add_systems(PostUpdate, rebuild_batches.after(sync_world));
+    }
+}
+
+/// `Scenes` spawns the full scene of every tile, `Batched` merges the floors per chunk.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TileRendering {
+    Scenes,
+    #[default]
+    Batched,
+}
+
+impl TileRendering {
+    /// Whether `has` is drawn by the chunk it's in rather than a scene of its own.
+    pub fn is_batched(&self, has: &PointType) -> bool {
+        *self == Self::Batched && has.layer() == Layer::Floor
+    }
+}
+
+/// Merged floors of a chunk that all come from the same model and material.
+#[derive(Component)]
+pub struct TileBatch(pub PointType);
+
+/// A mesh of a catalog model and where it sits in the model.
+struct Part {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+    transform: Transform,
+}
+
+/// The meshes each catalog model is made of, read once out of its scene.
+#[derive(Resource, Default)]
+pub struct ModelMeshes {
+    scenes: HashMap<&'static str, Handle<Scene>>,
+    parts: HashMap<&'static str, Vec<Part>>,
+}
+
+impl ModelMeshes {
+    /// Returns whether the parts of the model at `path` are known already, they're read once
+    /// it's loaded.
+    pub fn request(&mut self, cache: &ModelCache, path: &'static str) -> bool {
+        if self.parts.contains_key(path) {
+            return true;
+        }
+
+        // Asked for again until it's read, a model that failed to load becomes the placeholder.
+        self.scenes.insert(path, cache.scene(path));
+
+        false
+    }
+
+    /// Forgets the parts of the model at `path`, returns whether they were known.
+    pub(super) fn forget(&mut self, path: &'static str) -> bool {
+        self.scenes.remove(path);
+        self.parts.remove(path).is_some()
+    }
+
+    /// Corners of the box around the model at `path` and the material most of it is drawn
+    /// with, `None` until the model is loaded.
+    pub fn bounds(
+        &self,
+        path: &'static str,
+        meshes: &Assets<Mesh>,
+    ) -> Option<(Vec3, Vec3, Handle<StandardMaterial>)> {
+        let parts = self.parts.get(path)?;
+        let mut min = Vec3::splat(f32::MAX);
+        let mut max = Vec3::splat(f32::MIN);
+
+        for part in parts.iter() {
+            let Some(aabb) = meshes.get(&part.mesh).and_then(Mesh::compute_aabb) else {
+                continue;
+            };
+
+            for corner in corners(&aabb) {
+                let corner = part.transform.transform_point(corner);
+
+                min = min.min(corner);
+                max = max.max(corner);
+            }
+        }
+
+        if min.cmpgt(max).any() {
+            return None;
+        }
+
+        let material = parts
+            .iter()
+            .max_by_key(|part| {
+                meshes
+                    .get(&part.mesh)
+                    .map_or(0, |mesh| mesh.count_vertices())
+            })?
+            .material
+            .clone();
+
+        Some((min, max, material))
+    }
+}
+
+fn corners(aabb: &Aabb) -> [Vec3; 8] {
+    let min = Vec3::from(aabb.min());
+    let max = Vec3::from(aabb.max());
+
+    [
+        Vec3::new(min.x, min.y, min.z),
+        Vec3::new(max.x, min.y, min.z),
+        Vec3::new(min.x, max.y, min.z),
+        Vec3::new(max.x, max.y, min.z),
+        Vec3::new(min.x, min.y, max.z),
+        Vec3::new(max.x, min.y, max.z),
+        Vec3::new(min.x, max.y, max.z),
+        Vec3::new(max.x, max.y, max.z),
+    ]
+}
+
+/// Version of the chunk each spawned chunk's batches were built from, and the batches.
+///
+/// `terrain` The cells the batches were last built on, to tell which chunks an edit touched.
+#[derive(Resource, Default)]
+struct Batches {
+    built: HashMap<Position, (u64, Vec<Entity>)>,
+    terrain: HashMap<Position, Elevation>,
+}
+
+impl Batches {
+    fn invalidate(&mut self, chunk: &Position) {
+        if let Some((version, _)) = self.built.get_mut(chunk) {
+            *version = u64::MAX;
+        }
+    }
+
+    /// Marks the chunks with cells that changed height since the last time as out of date.
+    fn update_terrain(&mut self, terrain: &Terrain) {
+        let changed: Vec<Position> = terrain
+            .cells
+            .iter()
+            .filter(|(cell, elevation)| self.terrain.get(*cell) != Some(*elevation))
+            .map(|(cell, _)| *cell)
+            .chain(
+                self.terrain
+                    .keys()
+                    .filter(|cell| !terrain.cells.contains_key(*cell))
+                    .copied(),
+            )
+            .collect();
+
+        for cell in changed {
+            self.invalidate(&World::chunk_of(&cell));
+        }
+
+        self.terrain = terrain.cells.clone();
+    }
+}
+
+/// Reads reloaded models again and rebuilds every batch with them.
+fn reload_models(
+    mut events: EventReader<AssetEvent<Scene>>,
+    cache: Res<ModelCache>,
+    mut models: ResMut<ModelMeshes>,
+    mut batches: ResMut<Batches>,
+) {
+    let mut reloaded = false;
+
+    for event in events.iter() {
+        let AssetEvent::Modified { handle } = event else {
+            continue;
+        };
+
+        if let Some(path) = cache.path_of(handle) {
+            reloaded |= models.forget(path);
+        }
+    }
+
+    if reloaded {
+        for (version, _) in batches.built.values_mut() {
+            *version = u64::MAX;
+        }
+    }
+}
+
+fn resolve_models(mut models: ResMut<ModelMeshes>, scenes: Res<Assets<Scene>>) {
+    let resolved: Vec<(&'static str, Vec<Part>)> = models
+        .scenes
+        .iter()
+        .filter(|(path, _)| !models.parts.contains_key(*path))
+        .filter_map(|(path, handle)| Some((*path, scene_parts(&scenes.get(handle)?.world))))
+        .collect();
+
+    for (path, parts) in resolved {
+        models.parts.insert(path, parts);
+    }
+}
+
+/// Every mesh in a scene with its transform from the root of the scene.
+fn scene_parts(world: &bevy::ecs::world::World) -> Vec<Part> {
+    world
+        .iter_entities()
+        .filter_map(|entity| {
+            let mesh = entity.get::<Handle<Mesh>>()?;
+            let material = entity.get::<Handle<StandardMaterial>>()?;
+
+            let mut transform = entity.get::<Transform>().copied().unwrap_or_default();
+            let mut current = entity.id();
+
+            while let Some(parent) = world.get::<Parent>(current) {
+                current = parent.get();
+
+                if let Some(parent_transform) = world.get::<Transform>(current) {
+                    transform = parent_transform.mul_transform(transform);
+                }
+            }
+
+            Some(Part {
+                mesh: mesh.clone(),
+                material: material.clone(),
+                transform,
+            })
+        })
+        .collect()
+}
+
+fn rebuild_batches(
+    mut commands: Commands,
+    rendering: Res<TileRendering>,
+    config: Res<StreamConfig>,
+    world: Res<World>,
+    loaded: Res<LoadedChunks>,
+    grid: Res<Grid>,
+    terrain: Res<Terrain>,
+    cache: Res<ModelCache>,
+    mut models: ResMut<ModelMeshes>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut batches: ResMut<Batches>,
+) {
+    // The batches of unloaded chunks went with the chunk.
+    batches.built.retain(|chunk, _| loaded.is_loaded(chunk));
+
+    // The tiles sit on the terrain, the chunks with edited cells go out of date with it.
+    if terrain.is_changed() {
+        batches.update_terrain(&terrain);
+    }
+
+    if grid.is_changed() {
+        for (version, _) in batches.built.values_mut() {
+            *version = u64::MAX;
+        }
+    }
+
+    let mut budget = config.rebuilds_per_frame;
+
+    for (chunk, root) in loaded.roots() {
+        // The rest waits for the next frames.
+        if budget == 0 {
+            break;
+        }
+
+        let version = world.chunk(chunk).map_or(0, |data| data.version);
+
+        if batches
+            .built
+            .get(chunk)
+            .is_some_and(|(built, _)| *built == version)
+        {
+            continue;
+        }
+
+        let floors: Vec<&Point> = world
+            .chunk(chunk)
+            .into_iter()
+            .flat_map(|data| data.points.iter())
+            .filter(|point| rendering.is_batched(&point.has))
+            .collect();
+
+        // Waits for every model the chunk uses to load, tried again next frame.
+        let mut ready = true;
+
+        for point in floors.iter() {
+            ready &= models.request(&cache, point.has.meta().path);
+        }
+
+        if !ready {
+            continue;
+        }
+
+        budget -= 1;
+
+        let mut builders: HashMap<(PointType, Handle<StandardMaterial>), MeshBuilder> =
+            HashMap::new();
+
+        for point in floors {
+            let tile = terrain.cell_transform(&grid, &point.position, &point.orientation);
+
+            for part in models.parts[point.has.meta().path].iter() {
+                let Some(mesh) = meshes.get(&part.mesh) else {
+                    continue;
+                };
+
+                builders
+                    .entry((point.has.clone(), part.material.clone()))
+                    .or_default()
+                    .append(mesh, tile.mul_transform(part.transform));
+            }
+        }
+
+        if let Some((_, old)) = batches.built.remove(chunk) {
+            for entity in old {
+                commands.entity(entity).despawn();
+            }
+        }
+
+        let mut entities = vec![];
+
+        for ((has, material), builder) in builders {
+            let entity = commands
+                .spawn((
+                    PbrBundle {
+                        mesh: meshes.add(builder.build()),
+                        material,
+                        ..default()
+                    },
+                    TileBatch(has),
+                ))
+                .id();
+
+            commands.entity(*root).add_child(entity);
+            entities.push(entity);
+        }
+
+        batches.built.insert(*chunk, (version, entities));
+    }
+}
+
+/// Meshes merged into one, tangents are only kept when every mesh had them.
+struct MeshBuilder {
+    positions: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+    uvs: Vec<[f32; 2]>,
+    tangents: Option<Vec<[f32; 4]>>,
+    indices: Vec<u32>,
+}
+
+impl Default for MeshBuilder {
+    fn default() -> Self {
+        Self {
+            positions: vec![],
+            normals: vec![],
+            uvs: vec![],
+            tangents: Some(vec![]),
+            indices: vec![],
+        }
+    }
+}
+
+impl MeshBuilder {
+    fn append(&mut self, mesh: &Mesh, transform: Transform) {
+        if mesh.primitive_topology() != PrimitiveTopology::TriangleList {
+            return;
+        }
+
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            return;
+        };
+
+        let matrix = transform.compute_matrix();
+        let rotation = Mat3::from_mat4(matrix);
+        let normal_matrix = rotation.inverse().transpose();
+        let first = self.positions.len() as u32;
+        let count = positions.len();
+
+        self.positions.extend(
+            positions
+                .iter()
+                .map(|position| matrix.transform_point3(Vec3::from(*position)).to_array()),
+        );
+
+        match mesh.attribute(Mesh::ATTRIBUTE_NORMAL) {
+            Some(VertexAttributeValues::Float32x3(normals)) => {
+                self.normals.extend(normals.iter().map(|normal| {
+                    (normal_matrix * Vec3::from(*normal))
+                        .normalize_or_zero()
+                        .to_array()
+                }))
+            }
+            _ => self
+                .normals
+                .extend(std::iter::repeat_n([0., 1., 0.], count)),
+        }
+
+        match mesh.attribute(Mesh::ATTRIBUTE_UV_0) {
+            Some(VertexAttributeValues::Float32x2(uvs)) => self.uvs.extend(uvs),
+            _ => self.uvs.extend(std::iter::repeat_n([0., 0.], count)),
+        }
+
+        match (
+            self.tangents.as_mut(),
+            mesh.attribute(Mesh::ATTRIBUTE_TANGENT),
+        ) {
+            (Some(tangents), Some(VertexAttributeValues::Float32x4(source))) => {
+                tangents.extend(source.iter().map(|[x, y, z, w]| {
+                    let tangent = (rotation * Vec3::new(*x, *y, *z)).normalize_or_zero();
+
+                    [tangent.x, tangent.y, tangent.z, *w]
+                }))
+            }
+            _ => self.tangents = None,
+        }
+
+        match mesh.indices() {
+            Some(indices) => self
+                .indices
+                .extend(indices.iter().map(|index| first + index as u32)),
+            None => self.indices.extend(first..first + count as u32),
+        }
+    }
+
+    fn build(self) -> Mesh {
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, self.positions);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, self.normals);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, self.uvs);
+
+        if let Some(tangents) = self.tangents {
+            mesh.insert_attribute(Mesh::ATTRIBUTE_TANGENT, tangents);
+        }
+
+        mesh.set_indices(Some(Indices::U32(self.indices)));
+        mesh
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn terrain_edits_only_invalidate_their_chunks() {
+        let mut batches = Batches::default();
+        let mut terrain = Terrain::default();
+        let raised = Elevation {
+            level: 1,
+            ramp: None,
+        };
+
+        terrain.set(Position::new(3, 3), raised);
+        batches.update_terrain(&terrain);
+
+        for chunk in [
+            Position::new(0, 0),
+            Position::new(1, 0),
+            Position::new(0, 1),
+        ] {
+            batches.built.insert(chunk, (1, vec![]));
+        }
+
+        // Raised in the second chunk, lowered back to flat in the first.
+        terrain.set(Position::new(20, 3), raised);
+        terrain.set(Position::new(3, 3), Elevation::default());
+        batches.update_terrain(&terrain);
+
+        let version = |chunk| batches.built[&chunk].0;
+
+        assert_eq!(version(Position::new(0, 0)), u64::MAX);
+        assert_eq!(version(Position::new(1, 0)), u64::MAX);
+        assert_eq!(version(Position::new(0, 1)), 1);
+    }
+}
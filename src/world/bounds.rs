@@ -0,0 +1,215 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::prelude::*;
+
+use crate::{
+    controls::{
+        bookmarks::Bookmarks,
+        input_map::{Action, Actions},
+        mouse_projection::MouseProjection,
+        selection::Selection,
+    },
+    simulation::{growth::GrowthProgress, zoning::Zones},
+};
+
+use super::{
+    chunks::LoadedChunks, grid::Grid, point::Position, terrain::Terrain, water::Water, World,
+};
+
+/// Limits the map to the bounds of the [`Grid`] when it has them.
+///
+/// The border is drawn around the map and the cell under the cursor is crossed out when
+/// it's outside. `PageUp` grows the map by a cell on every side, starting from the cells in
+/// use if it had no bounds yet, and `PageDown` shrinks it. `Ctrl` and the arrow keys move
+/// everything on the map a cell north, east, south or west, the bounds stay where they are.
+pub struct BoundsPlugin;
+
+impl Plugin for BoundsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ResizeMap>();
+        app.add_systems(
+            Update,
+            (
+                (resize_keys, resize_map).chain(),
+                draw_bounds,
+                draw_outside_cursor,
+            ),
+        );
+    }
+}
+
+/// Changes the bounds of the map to `bounds` after moving everything on it by `offset`
+/// cells. Whatever ends up outside the new bounds is removed.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ResizeMap {
+    pub bounds: Option<(Position, Position)>,
+    pub offset: Position,
+}
+
+fn resize_keys(
+    actions: Res<Actions>,
+    grid: Res<Grid>,
+    world: Res<World>,
+    mut resize_evw: EventWriter<ResizeMap>,
+) {
+    let shift = (0..4).find(|side| actions.just_pressed(Action::ShiftMap(*side)));
+
+    if let Some(side) = shift {
+        resize_evw.send(ResizeMap {
+            bounds: grid.bounds,
+            offset: Position::default().neighbours()[side],
+        });
+
+        return;
+    }
+
+    let grow = actions.just_pressed(Action::ExpandMap);
+    let shrink = actions.just_pressed(Action::ShrinkMap);
+
+    if !grow && !shrink {
+        return;
+    }
+
+    let bounds = match grid.bounds {
+        Some((min, max)) if grow => Some((
+            Position::new(min.x - 1, min.y - 1),
+            Position::new(max.x + 1, max.y + 1),
+        )),
+        // Never shrinks past a single cell.
+        Some((min, max)) if max.x - min.x >= 2 && max.y - min.y >= 2 => Some((
+            Position::new(min.x + 1, min.y + 1),
+            Position::new(max.x - 1, max.y - 1),
+        )),
+        Some(_) => return,
+        None if grow => {
+            let (mut min, mut max) = (Position::new(-5, -5), Position::new(5, 5));
+
+            for point in world.points() {
+                min = Position::new(min.x.min(point.position.x), min.y.min(point.position.y));
+                max = Position::new(max.x.max(point.position.x), max.y.max(point.position.y));
+            }
+
+            Some((min, max))
+        }
+        None => return,
+    };
+
+    resize_evw.send(ResizeMap {
+        bounds,
+        offset: Position::default(),
+    });
+}
+
+fn resize_map(
+    mut commands: Commands,
+    mut resize_evr: EventReader<ResizeMap>,
+    mut grid: ResMut<Grid>,
+    mut world: ResMut<World>,
+    mut loaded: ResMut<LoadedChunks>,
+    mut zones: ResMut<Zones>,
+    mut terrain: ResMut<Terrain>,
+    mut water: ResMut<Water>,
+    mut progress: ResMut<GrowthProgress>,
+    mut selection: ResMut<Selection>,
+    mut bookmarks: ResMut<Bookmarks>,
+) {
+    for resize in resize_evr.iter() {
+        grid.bounds = resize.bounds;
+
+        let offset = resize.offset;
+        let moved = |pos: &Position| Position::new(pos.x + offset.x, pos.y + offset.y);
+
+        // The chunks are spawned again from the world once it's been changed.
+        loaded.unload_all(&mut commands);
+
+        world.update_points(|mut point| {
+            point.position = moved(&point.position);
+
+            grid.contains(&point.position).then_some(point)
+        });
+
+        zones.cells = zones
+            .cells
+            .drain()
+            .map(|(pos, zone)| (moved(&pos), zone))
+            .filter(|(pos, _)| grid.contains(pos))
+            .collect();
+
+        terrain.cells = terrain
+            .cells
+            .drain()
+            .map(|(pos, elevation)| (moved(&pos), elevation))
+            .filter(|(pos, _)| grid.contains(pos))
+            .collect();
+
+        water.cells = water
+            .cells
+            .drain()
+            .map(|pos| moved(&pos))
+            .filter(|pos| grid.contains(pos))
+            .collect();
+
+        progress.cells = progress
+            .cells
+            .drain()
+            .map(|(pos, amount)| (moved(&pos), amount))
+            .filter(|(pos, _)| grid.contains(pos))
+            .collect();
+
+        selection.cells = selection
+            .cells
+            .drain()
+            .map(|pos| moved(&pos))
+            .filter(|pos| grid.contains(pos))
+            .collect();
+
+        // Bookmarks keep looking at the same things.
+        let shift = Vec3::new(offset.x as f32, 0., offset.y as f32) * grid.cell_size;
+
+        for bookmark in bookmarks.list.iter_mut() {
+            bookmark.orbit.pivot += shift;
+        }
+    }
+}
+
+fn draw_bounds(grid: Res<Grid>, mut gizmos: Gizmos) {
+    let Some((min, max)) = grid.bounds else {
+        return;
+    };
+
+    let half = grid.cell_size / 2.;
+    let min = grid.cell_center(&min) - Vec3::new(half, 0., half);
+    let max = grid.cell_center(&max) + Vec3::new(half, 0., half);
+
+    gizmos.rect(
+        (min + max) / 2. + Vec3::Y * 0.2,
+        Quat::from_rotation_x(FRAC_PI_2),
+        Vec2::new(max.x - min.x, max.z - min.z),
+        Color::ORANGE_RED,
+    );
+}
+
+fn draw_outside_cursor(
+    grid: Res<Grid>,
+    terrain: Res<Terrain>,
+    mouse_projection: Res<MouseProjection>,
+    mut gizmos: Gizmos,
+) {
+    if !mouse_projection.hit || grid.contains(&mouse_projection.normal) {
+        return;
+    }
+
+    let center = terrain.cell_center(&grid, &mouse_projection.normal) + Vec3::Y * 0.2;
+    let half = grid.cell_size * 0.4;
+
+    gizmos.line(
+        center + Vec3::new(-half, 0., -half),
+        center + Vec3::new(half, 0., half),
+        Color::RED,
+    );
+    gizmos.line(
+        center + Vec3::new(-half, 0., half),
+        center + Vec3::new(half, 0., -half),
+        Color::RED,
+    );
+}
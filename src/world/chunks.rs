@@ -0,0 +1,461 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::{
+    prelude::*,
+    render::{mesh::Indices, render_resource::PrimitiveTopology},
+};
+
+use crate::{
+    controls::{
+        movement::ControlData,
+        walk::{CameraView, WalkData},
+    },
+    models::ModelCache,
+};
+
+use super::{
+    batching::TileRendering,
+    grid::Grid,
+    point::{Layer, Point, PointType, Position},
+    spawn_point,
+    terrain::Terrain,
+    World, CHUNK_SIZE,
+};
+
+/// Spawns the chunks of the [`World`] around the camera and despawns the ones it leaves
+/// behind. Chunks further out are drawn as a single low detail mesh each.
+///
+/// Points can still be spawned anywhere, like when placing or growing, they're moved into
+/// their chunk, or only kept in the [`World`] when that chunk isn't spawned.
+pub struct ChunkPlugin;
+
+impl Plugin for ChunkPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(StreamConfig::default());
+        app.insert_resource(LoadedChunks::default());
+        app.insert_resource(Impostors::default());
+        app.add_systems(Startup, setup);
+        app.add_systems(Update, (stream_chunks, update_impostors));
+        app.add_systems(PostUpdate, sync_world);
+    }
+}
+
+/// `load_radii` How far from the middle of the view chunks are spawned, in camera radii.
+/// They're only despawned a chunk further out, so going back and forth over the edge
+/// doesn't keep respawning them.
+///
+/// `far_radii` How far out chunks are still drawn in low detail.
+///
+/// `walk_radius` Stands in for the camera radius while walking.
+///
+/// `loads_per_frame`, `impostors_per_frame` and `rebuilds_per_frame` How many chunks get
+/// spawned, have their low detail mesh built or their floors merged again in a frame at
+/// most, to spread the work out.
+#[derive(Resource, Debug)]
+pub struct StreamConfig {
+    pub load_radii: f32,
+    pub far_radii: f32,
+    pub walk_radius: f32,
+    pub loads_per_frame: usize,
+    pub impostors_per_frame: usize,
+    pub rebuilds_per_frame: usize,
+}
+
+impl Default for StreamConfig {
+    fn default() -> Self {
+        Self {
+            load_radii: 2.,
+            far_radii: 8.,
+            walk_radius: 150.,
+            loads_per_frame: 2,
+            impostors_per_frame: 8,
+            rebuilds_per_frame: 4,
+        }
+    }
+}
+
+/// The spawned chunks and which chunk each spawned point is in.
+#[derive(Resource, Default)]
+pub struct LoadedChunks {
+    roots: HashMap<Position, Entity>,
+    points: HashMap<Entity, Position>,
+}
+
+impl LoadedChunks {
+    pub fn is_loaded(&self, chunk: &Position) -> bool {
+        self.roots.contains_key(chunk)
+    }
+
+    /// Each spawned chunk and the entity its points are parented to.
+    pub fn roots(&self) -> impl Iterator<Item = (&Position, &Entity)> {
+        self.roots.iter()
+    }
+
+    /// Despawns every chunk, they're spawned again from the [`World`] as they're needed.
+    pub fn unload_all(&mut self, commands: &mut Commands) {
+        for (_, root) in self.roots.drain() {
+            commands.entity(root).despawn_recursive();
+        }
+
+        self.points.clear();
+    }
+
+    fn unload(&mut self, commands: &mut Commands, chunk: &Position) {
+        if let Some(root) = self.roots.remove(chunk) {
+            commands.entity(root).despawn_recursive();
+        }
+
+        self.points.retain(|_, point_chunk| point_chunk != chunk);
+    }
+}
+
+/// Parent of the points of a spawned chunk.
+#[derive(Component)]
+pub struct ChunkRoot(pub Position);
+
+/// The low detail meshes and the version of the chunk each was built from.
+#[derive(Resource, Default)]
+struct Impostors {
+    material: Handle<StandardMaterial>,
+    spawned: HashMap<Position, (Entity, u64)>,
+}
+
+fn setup(mut impostors: ResMut<Impostors>, mut materials: ResMut<Assets<StandardMaterial>>) {
+    // Colored per vertex.
+    impostors.material = materials.add(StandardMaterial {
+        base_color: Color::WHITE,
+        perceptual_roughness: 1.,
+        ..default()
+    });
+}
+
+/// Middle of what the camera is looking at and the radius to measure distances with.
+fn view_center(
+    view: &State<CameraView>,
+    control_data: &ControlData,
+    walk_data: &WalkData,
+    config: &StreamConfig,
+) -> (Vec3, f32) {
+    match view.get() {
+        CameraView::Orbit => (control_data.current.pivot, control_data.current.radius),
+        CameraView::Walk => (walk_data.position, config.walk_radius),
+    }
+}
+
+/// Distance on the ground from `center` to the closest part of `chunk`.
+fn chunk_distance(grid: &Grid, chunk: &Position, center: Vec3) -> f32 {
+    let size = CHUNK_SIZE as f32 * grid.cell_size;
+    let first = Position::new(chunk.x * CHUNK_SIZE, chunk.y * CHUNK_SIZE);
+    let min = grid.cell_center(&first) - Vec3::new(1., 0., 1.) * grid.cell_size / 2.;
+
+    let dx = (min.x - center.x).max(center.x - (min.x + size)).max(0.);
+    let dz = (min.z - center.z).max(center.z - (min.z + size)).max(0.);
+
+    Vec2::new(dx, dz).length()
+}
+
+fn stream_chunks(
+    mut commands: Commands,
+    config: Res<StreamConfig>,
+    view: Res<State<CameraView>>,
+    control_data: Res<ControlData>,
+    walk_data: Res<WalkData>,
+    grid: Res<Grid>,
+    cache: Res<ModelCache>,
+    rendering: Res<TileRendering>,
+    world: Res<World>,
+    mut loaded: ResMut<LoadedChunks>,
+) {
+    let (center, radius) = view_center(&view, &control_data, &walk_data, &config);
+    let size = CHUNK_SIZE as f32 * grid.cell_size;
+    let load = (radius * config.load_radii).max(size);
+
+    let left_behind: Vec<Position> = loaded
+        .roots
+        .keys()
+        .filter(|chunk| chunk_distance(&grid, chunk, center) > load + size)
+        .copied()
+        .collect();
+
+    for chunk in left_behind {
+        loaded.unload(&mut commands, &chunk);
+    }
+
+    // Every chunk in range gets a root even when it's empty, so points placed there have
+    // somewhere to go.
+    let reach = (load / size).ceil() as i32 + 1;
+    let middle = World::chunk_of(&grid.cell_at(center));
+    let mut wanted: Vec<(f32, Position)> = vec![];
+
+    for x in -reach..=reach {
+        for y in -reach..=reach {
+            let chunk = Position::new(middle.x + x, middle.y + y);
+            let distance = chunk_distance(&grid, &chunk, center);
+
+            if distance <= load && !loaded.is_loaded(&chunk) {
+                wanted.push((distance, chunk));
+            }
+        }
+    }
+
+    wanted.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    for (_, chunk) in wanted.into_iter().take(config.loads_per_frame) {
+        let root = commands
+            .spawn((SpatialBundle::default(), ChunkRoot(chunk)))
+            .id();
+
+        if let Some(data) = world.chunk(&chunk) {
+            for point in data.points.iter() {
+                let entity = spawn_point(&mut commands, &cache, &grid, &rendering, point.clone());
+
+                commands.entity(root).add_child(entity);
+                loaded.points.insert(entity, chunk);
+            }
+        }
+
+        loaded.roots.insert(chunk, root);
+    }
+}
+
+/// Keeps the [`World`] up to date with the spawned points, and puts points spawned on their
+/// own into their chunk.
+pub(super) fn sync_world(
+    mut commands: Commands,
+    mut world: ResMut<World>,
+    mut loaded: ResMut<LoadedChunks>,
+    changed: Query<(Entity, Ref<Point>), Changed<Point>>,
+    points: Query<&Point>,
+    mut removed: RemovedComponents<Point>,
+) {
+    let mut touched: HashSet<Position> = HashSet::new();
+
+    // Points despawned along with their chunk aren't tracked anymore, so only the ones that
+    // were actually removed count.
+    for entity in removed.iter() {
+        if let Some(chunk) = loaded.points.remove(&entity) {
+            touched.insert(chunk);
+        }
+    }
+
+    for (entity, point) in changed.iter() {
+        let chunk = World::chunk_of(&point.position);
+        let previous = loaded.points.get(&entity).copied();
+
+        if previous == Some(chunk) {
+            // Spawned along with its chunk, the world already has it.
+            if !point.is_added() {
+                touched.insert(chunk);
+            }
+
+            continue;
+        }
+
+        if let Some(previous) = previous {
+            loaded.points.remove(&entity);
+            touched.insert(previous);
+        }
+
+        match loaded.roots.get(&chunk) {
+            Some(root) => {
+                commands.entity(*root).add_child(entity);
+                loaded.points.insert(entity, chunk);
+                touched.insert(chunk);
+            }
+            None => {
+                world.insert(point.clone());
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+    }
+
+    for chunk in touched {
+        let chunk_points = loaded
+            .points
+            .iter()
+            .filter(|(_, point_chunk)| **point_chunk == chunk)
+            .filter_map(|(entity, _)| points.get(*entity).ok())
+            .cloned()
+            .collect();
+
+        world.set_chunk(chunk, chunk_points);
+    }
+}
+
+fn update_impostors(
+    mut commands: Commands,
+    config: Res<StreamConfig>,
+    view: Res<State<CameraView>>,
+    control_data: Res<ControlData>,
+    walk_data: Res<WalkData>,
+    grid: Res<Grid>,
+    terrain: Res<Terrain>,
+    world: Res<World>,
+    loaded: Res<LoadedChunks>,
+    mut impostors: ResMut<Impostors>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let (center, radius) = view_center(&view, &control_data, &walk_data, &config);
+    let size = CHUNK_SIZE as f32 * grid.cell_size;
+    let far = radius * config.far_radii;
+
+    let is_wanted = |chunk: &Position, margin: f32| {
+        !loaded.is_loaded(chunk)
+            && world
+                .chunk(chunk)
+                .is_some_and(|data| !data.points.is_empty())
+            && chunk_distance(&grid, chunk, center) <= far + margin
+    };
+
+    impostors.spawned.retain(|chunk, (entity, _)| {
+        let keep = is_wanted(chunk, size);
+
+        if !keep {
+            commands.entity(*entity).despawn();
+        }
+
+        keep
+    });
+
+    // The meshes sit on the terrain, so they all go out of date with it.
+    if terrain.is_changed() || grid.is_changed() {
+        for (_, version) in impostors.spawned.values_mut() {
+            *version = 0;
+        }
+    }
+
+    let mut stale: Vec<(f32, Position)> = world
+        .chunks()
+        .filter(|(chunk, data)| {
+            is_wanted(chunk, 0.)
+                && impostors
+                    .spawned
+                    .get(chunk)
+                    .map_or(true, |(_, version)| *version != data.version)
+        })
+        .map(|(chunk, _)| (chunk_distance(&grid, chunk, center), *chunk))
+        .collect();
+
+    stale.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    for (_, chunk) in stale.into_iter().take(config.impostors_per_frame) {
+        let data = world.chunk(&chunk).unwrap();
+
+        if let Some((entity, _)) = impostors.spawned.remove(&chunk) {
+            commands.entity(entity).despawn();
+        }
+
+        let entity = commands
+            .spawn(PbrBundle {
+                mesh: meshes.add(impostor_mesh(&grid, &terrain, &data.points)),
+                material: impostors.material.clone(),
+                ..default()
+            })
+            .id();
+
+        impostors.spawned.insert(chunk, (entity, data.version));
+    }
+}
+
+/// Colored tiles for the floors and boxes for whatever stands on them.
+fn impostor_mesh(grid: &Grid, terrain: &Terrain, points: &[Point]) -> Mesh {
+    let mut positions: Vec<[f32; 3]> = vec![];
+    let mut normals: Vec<[f32; 3]> = vec![];
+    let mut colors: Vec<[f32; 4]> = vec![];
+    let mut indices: Vec<u32> = vec![];
+
+    let mut quad = |corners: [Vec3; 4], normal: Vec3, color: Color| {
+        let first = positions.len() as u32;
+
+        for corner in corners {
+            positions.push(corner.to_array());
+            normals.push(normal.to_array());
+            colors.push(color.as_linear_rgba_f32());
+        }
+
+        indices.extend([first, first + 1, first + 2, first, first + 2, first + 3]);
+    };
+
+    for point in points {
+        let center = terrain.cell_center(grid, &point.position);
+
+        let (half, height, color) = match point.has.layer() {
+            Layer::Floor => {
+                let color = match point.has {
+                    PointType::Grass => Color::rgb(0.3, 0.55, 0.25),
+                    PointType::Concrete => Color::rgb(0.55, 0.55, 0.55),
+                    _ => Color::rgb(0.3, 0.3, 0.32),
+                };
+
+                (0.5, 0.05, color)
+            }
+            Layer::Building => (0.4, 1.2, Color::rgb(0.78, 0.72, 0.65)),
+            Layer::Nature => (0.2, 0.6, Color::rgb(0.15, 0.4, 0.15)),
+        };
+
+        let half = half * grid.cell_size;
+        let height = height * grid.cell_size;
+        let min = center - Vec3::new(half, 0., half);
+        let max = center + Vec3::new(half, height, half);
+
+        quad(
+            [
+                Vec3::new(min.x, max.y, min.z),
+                Vec3::new(min.x, max.y, max.z),
+                Vec3::new(max.x, max.y, max.z),
+                Vec3::new(max.x, max.y, min.z),
+            ],
+            Vec3::Y,
+            color,
+        );
+
+        if point.has.layer() == Layer::Floor {
+            continue;
+        }
+
+        // Going around the box, each side faces away from the middle.
+        let around = [
+            (
+                Vec3::new(min.x, 0., min.z),
+                Vec3::new(max.x, 0., min.z),
+                Vec3::NEG_Z,
+            ),
+            (
+                Vec3::new(max.x, 0., min.z),
+                Vec3::new(max.x, 0., max.z),
+                Vec3::X,
+            ),
+            (
+                Vec3::new(max.x, 0., max.z),
+                Vec3::new(min.x, 0., max.z),
+                Vec3::Z,
+            ),
+            (
+                Vec3::new(min.x, 0., max.z),
+                Vec3::new(min.x, 0., min.z),
+                Vec3::NEG_X,
+            ),
+        ];
+
+        for (a, b, normal) in around {
+            quad(
+                [
+                    a + Vec3::Y * min.y,
+                    a + Vec3::Y * max.y,
+                    b + Vec3::Y * max.y,
+                    b + Vec3::Y * min.y,
+                ],
+                normal,
+                color,
+            );
+        }
+    }
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh
+}
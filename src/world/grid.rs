@@ -0,0 +1,57 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::point::Position;
+
+/// How cells are laid out in the world, saved with the map so models made for a different
+/// tile size line up.
+///
+/// `cell_size` Width of a cell in world units, cells are centered on multiples of it.
+///
+/// `origin` Where the center of cell `0, 0` is.
+///
+/// `bounds` The first and last cell of the map on each axis, `None` for no limit.
+///
+/// `level_height` How much higher each terrain level is than the one below.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Grid {
+    pub cell_size: f32,
+    pub origin: Vec3,
+    pub bounds: Option<(Position, Position)>,
+    pub level_height: f32,
+}
+
+impl Default for Grid {
+    fn default() -> Self {
+        Self {
+            cell_size: 20.,
+            origin: Vec3::ZERO,
+            bounds: None,
+            level_height: 5.,
+        }
+    }
+}
+
+impl Grid {
+    /// World position of the center of `cell`.
+    pub fn cell_center(&self, cell: &Position) -> Vec3 {
+        self.origin + Vec3::new(cell.x as f32, 0., cell.y as f32) * self.cell_size
+    }
+
+    /// The cell `position` is over.
+    pub fn cell_at(&self, position: Vec3) -> Position {
+        let local = (position - self.origin) / self.cell_size;
+
+        Position::new(local.x.round() as i32, local.z.round() as i32)
+    }
+
+    pub fn contains(&self, cell: &Position) -> bool {
+        match &self.bounds {
+            Some((min, max)) => {
+                (min.x..=max.x).contains(&cell.x) && (min.y..=max.y).contains(&cell.y)
+            }
+            None => true,
+        }
+    }
+}
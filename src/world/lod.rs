@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::{controls::movement::MainCamera, models::ModelCache};
+
+use super::{
+    batching::ModelMeshes,
+    point::{Layer, Point},
+};
+
+/// Swaps buildings and nature for something simpler when the camera is far from them.
+///
+/// A model's far version is the `lod` of its catalog entry, or a box the size of the model
+/// in its main material when it doesn't have one.
+pub struct LodPlugin;
+
+impl Plugin for LodPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(LodConfig::default());
+        app.insert_resource(Impostors::default());
+        app.add_systems(Update, (reload_impostors, add_lod, switch_lod).chain());
+    }
+}
+
+/// `distance` How far from the camera a model switches to its far version.
+///
+/// `hysteresis` How far past `distance` the camera has to go either way before a model
+/// switches, so models right at the edge don't flicker back and forth.
+#[derive(Resource, Debug)]
+pub struct LodConfig {
+    pub distance: f32,
+    pub hysteresis: f32,
+}
+
+impl Default for LodConfig {
+    fn default() -> Self {
+        Self {
+            distance: 200.,
+            hysteresis: 25.,
+        }
+    }
+}
+
+/// Whether a point is showing its far version.
+#[derive(Component, Default)]
+pub struct Lod {
+    pub far: bool,
+}
+
+/// The boxes standing in for models without a `lod` of their own.
+#[derive(Resource, Default)]
+struct Impostors {
+    scenes: HashMap<&'static str, Handle<Scene>>,
+}
+
+impl Impostors {
+    /// The box for the model at `path`, `None` while the model is still loading.
+    fn get(
+        &mut self,
+        path: &'static str,
+        cache: &ModelCache,
+        models: &mut ModelMeshes,
+        meshes: &mut Assets<Mesh>,
+        scenes: &mut Assets<Scene>,
+    ) -> Option<Handle<Scene>> {
+        if let Some(scene) = self.scenes.get(path) {
+            return Some(scene.clone());
+        }
+
+        if !models.request(cache, path) {
+            return None;
+        }
+
+        let (min, max, material) = models.bounds(path, meshes)?;
+        let mut world = bevy::ecs::world::World::new();
+
+        world.spawn(PbrBundle {
+            mesh: meshes.add(shape::Box::from_corners(min, max).into()),
+            material,
+            ..default()
+        });
+
+        let scene = scenes.add(Scene::new(world));
+
+        self.scenes.insert(path, scene.clone());
+        Some(scene)
+    }
+}
+
+/// Drops the boxes of reloaded models, models showing one go back to the full model until
+/// the new box is ready.
+fn reload_impostors(
+    mut events: EventReader<AssetEvent<Scene>>,
+    cache: Res<ModelCache>,
+    mut impostors: ResMut<Impostors>,
+    mut points: Query<(&Point, &mut Lod, &mut Handle<Scene>)>,
+) {
+    for event in events.iter() {
+        let AssetEvent::Modified { handle } = event else {
+            continue;
+        };
+
+        let Some(path) = cache.path_of(handle) else {
+            continue;
+        };
+
+        if impostors.scenes.remove(path).is_none() {
+            continue;
+        }
+
+        for (point, mut lod, mut scene) in points.iter_mut() {
+            let meta = point.has.meta();
+
+            if lod.far && meta.path == path && meta.lod.is_none() {
+                lod.far = false;
+                *scene = cache.scene(path);
+            }
+        }
+    }
+}
+
+fn add_lod(mut commands: Commands, points: Query<(Entity, &Point), Added<Handle<Scene>>>) {
+    for (entity, point) in points.iter() {
+        if point.has.layer() != Layer::Floor {
+            commands.entity(entity).insert(Lod::default());
+        }
+    }
+}
+
+fn switch_lod(
+    config: Res<LodConfig>,
+    cache: Res<ModelCache>,
+    mut models: ResMut<ModelMeshes>,
+    mut impostors: ResMut<Impostors>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut scenes: ResMut<Assets<Scene>>,
+    camera: Query<&GlobalTransform, With<MainCamera>>,
+    mut points: Query<(Ref<Point>, &GlobalTransform, &mut Lod, &mut Handle<Scene>)>,
+) {
+    let Ok(camera) = camera.get_single() else {
+        return;
+    };
+
+    let eye = camera.translation();
+
+    for (point, transform, mut lod, mut scene) in points.iter_mut() {
+        let distance = transform.translation().distance(eye);
+
+        let far = if lod.far {
+            distance > config.distance - config.hysteresis
+        } else {
+            distance > config.distance + config.hysteresis
+        };
+
+        // A changed point may have become another model, which needs its own version.
+        if far == lod.far && !point.is_changed() {
+            continue;
+        }
+
+        let meta = point.has.meta();
+
+        let wanted = match (far, meta.lod) {
+            (false, _) => cache.scene(meta.path),
+            (true, Some(path)) => cache.scene(path),
+            (true, None) => {
+                // Stays as it is until the model is loaded and can be measured.
+                let Some(impostor) =
+                    impostors.get(meta.path, &cache, &mut models, &mut meshes, &mut scenes)
+                else {
+                    continue;
+                };
+
+                impostor
+            }
+        };
+
+        lod.far = far;
+
+        if *scene != wanted {
+            *scene = wanted;
+        }
+    }
+}
@@ -1,7 +1,21 @@
+use std::collections::HashMap;
+
 use bevy::prelude::*;
 
+pub mod batching;
+pub mod bounds;
+pub mod chunks;
+pub mod grid;
+pub mod lod;
 pub mod point;
+pub mod roads;
+pub mod terrain;
+pub mod water;
+
+use crate::models::ModelCache;
 
+use batching::TileRendering;
+use grid::Grid;
 use point::{Point, Position};
 
 pub struct WorldPlugin;
@@ -9,35 +23,181 @@ pub struct WorldPlugin;
 impl Plugin for WorldPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(World::default());
+        app.insert_resource(Grid::default());
+        app.add_plugins((
+            batching::BatchingPlugin,
+            bounds::BoundsPlugin,
+            chunks::ChunkPlugin,
+            lod::LodPlugin,
+            roads::RoadsPlugin,
+            terrain::TerrainPlugin,
+            water::WaterPlugin,
+        ));
     }
 }
 
+/// Width of a chunk in cells, saves are split along them so changing it breaks old ones.
+pub const CHUNK_SIZE: i32 = 16;
+
+/// The points of a square of [`CHUNK_SIZE`] cells.
+///
+/// `dirty` Changed since the map was last saved.
+///
+/// `version` Different after every change, for whatever is built out of the chunk to tell
+/// it's out of date.
+#[derive(Default)]
+pub struct Chunk {
+    pub points: Vec<Point>,
+    pub dirty: bool,
+    pub version: u64,
+}
+
+/// Every placed point, split into chunks.
+///
+/// Only the chunks around the camera have [`Point`] entities, see [`chunks`], everything
+/// that needs the whole map like the simulation or saving goes through here instead.
 #[derive(Resource, Default)]
 pub struct World {
-    pub points: Vec<Point>,
+    chunks: HashMap<Position, Chunk>,
+    versions: u64,
 }
 
 impl World {
-    pub fn get_point(&self, pos: &Position) -> Option<&Point> {
-        let mut p: Option<&Point> = None;
-
-        for point in &self.points {
-            if &point.position == pos {
-                p = Some(point);
-                break;
-            }
-        }
+    /// The chunk `pos` is in.
+    pub fn chunk_of(pos: &Position) -> Position {
+        Position::new(pos.x.div_euclid(CHUNK_SIZE), pos.y.div_euclid(CHUNK_SIZE))
+    }
 
-        p
+    pub fn chunk(&self, chunk: &Position) -> Option<&Chunk> {
+        self.chunks.get(chunk)
     }
 
-    pub fn set_point(&mut self, point: Point) {
-        let p_in = self.get_point(&point.position);
+    /// Different after every change to any chunk.
+    pub fn version(&self) -> u64 {
+        self.versions
+    }
+
+    pub fn chunks(&self) -> impl Iterator<Item = (&Position, &Chunk)> {
+        self.chunks.iter()
+    }
 
-        if let Some(_) = p_in {
+    pub fn points(&self) -> impl Iterator<Item = &Point> {
+        self.chunks.values().flat_map(|chunk| chunk.points.iter())
+    }
+
+    pub fn get_point<'a>(&'a self, pos: &'a Position) -> Option<&'a Point> {
+        self.points_at(pos).next()
+    }
+
+    /// All the points on a cell, a cell can have a floor and something on top of it.
+    pub fn points_at<'a>(&'a self, pos: &'a Position) -> impl Iterator<Item = &'a Point> {
+        self.chunks
+            .get(&Self::chunk_of(pos))
+            .into_iter()
+            .flat_map(|chunk| chunk.points.iter())
+            .filter(move |point| &point.position == pos)
+    }
+
+    pub fn has_building(&self, pos: &Position) -> bool {
+        self.points_at(pos).any(|point| point.has.is_building())
+    }
+
+    pub fn set_point(&mut self, point: Point) {
+        if self.get_point(&point.position).is_some() {
             return;
         }
 
-        self.points.push(point);
+        self.insert(point);
+    }
+
+    pub fn insert(&mut self, point: Point) {
+        let chunk = Self::chunk_of(&point.position);
+
+        self.chunk_mut(chunk).points.push(point);
+    }
+
+    /// Replaces the points of `chunk`.
+    pub fn set_chunk(&mut self, chunk: Position, points: Vec<Point>) {
+        self.chunk_mut(chunk).points = points;
+    }
+
+    /// Puts back a chunk as it was read from the save, so it doesn't count as changed.
+    pub fn load_chunk(&mut self, chunk: Position, points: Vec<Point>) {
+        self.set_chunk(chunk, points);
+        self.chunks.get_mut(&chunk).unwrap().dirty = false;
+    }
+
+    /// Runs every point through `f`, dropping the ones it returns `None` for. Points can
+    /// move to other chunks.
+    pub fn update_points(&mut self, mut f: impl FnMut(Point) -> Option<Point>) {
+        let points: Vec<Point> = self
+            .chunks
+            .values_mut()
+            .flat_map(|chunk| chunk.points.drain(..))
+            .collect();
+
+        for chunk in self.chunks.keys().copied().collect::<Vec<_>>() {
+            self.chunk_mut(chunk);
+        }
+
+        for point in points.into_iter().filter_map(&mut f) {
+            self.insert(point);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+    }
+
+    /// Every chunk is on disk as it is now.
+    pub fn mark_saved(&mut self) {
+        self.chunks.retain(|_, chunk| !chunk.points.is_empty());
+
+        for chunk in self.chunks.values_mut() {
+            chunk.dirty = false;
+        }
+    }
+
+    /// The chunk, marked as changed.
+    fn chunk_mut(&mut self, chunk: Position) -> &mut Chunk {
+        self.versions += 1;
+
+        let chunk = self.chunks.entry(chunk).or_default();
+
+        chunk.dirty = true;
+        chunk.version = self.versions;
+        chunk
     }
-}
\ No newline at end of file
+}
+
+/// Spawns the model of a point on its cell, with the point attached so it can be saved
+/// and removed later.
+pub fn spawn_point(
+    commands: &mut Commands,
+    cache: &ModelCache,
+    grid: &Grid,
+    rendering: &TileRendering,
+    point: Point,
+) -> Entity {
+    let mut tf = Transform::from_translation(grid.cell_center(&point.position));
+
+    tf.rotation = Quat::from_rotation_y(point.orientation.rotation());
+
+    // Drawn by its chunk, see `batching`.
+    if rendering.is_batched(&point.has) {
+        return commands
+            .spawn((SpatialBundle::from_transform(tf), point))
+            .id();
+    }
+
+    commands
+        .spawn((
+            SceneBundle {
+                scene: cache.scene(point.has.meta().path),
+                transform: tf,
+                ..default()
+            },
+            point,
+        ))
+        .id()
+}
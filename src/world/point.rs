@@ -3,10 +3,10 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     controls::{model_cursor::ModelCursor, place_model::Orientation},
-    models::{BuildingModel, FloorModel, Meta},
+    models::{BuildingModel, Catalog, FloorModel, Meta, NatureModel},
 };
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Hash)]
 pub enum PointType {
     Grass,
     Concrete,
@@ -18,25 +18,30 @@ pub enum PointType {
     RoadCornerWalkable,
     RoadIntersection,
     RoadIntersectionWalkable,
+    /// A road over water, the road it was is kept to turn it back when the water drains.
+    Bridge(Box<PointType>),
 
     Blgd01_01,
     Blgd02_01,
+
+    Tree01,
+    Tree02,
 }
 
 impl From<FloorModel> for PointType {
     fn from(value: FloorModel) -> Self {
         match value {
-            FloorModel::Grass(_) => Self::Grass,
-            FloorModel::Concrete(_) => Self::Concrete,
-
-            FloorModel::RoadStraight(_) => Self::RoadStraight,
-            FloorModel::RoadStraightWalkable(_) => Self::RoadStraightWalkable,
-            FloorModel::RoadEnd(_) => Self::RoadEnd,
-            FloorModel::RoadStraightSideOpen(_) => Self::RoadStraightSideOpen,
-            FloorModel::RoadCorner(_) => Self::RoadCorner,
-            FloorModel::RoadCornerWalkable(_) => Self::RoadCornerWalkable,
-            FloorModel::RoadIntersection(_) => Self::RoadIntersection,
-            FloorModel::RoadIntersectionWalkable(_) => Self::RoadIntersectionWalkable,
+            FloorModel::Grass => Self::Grass,
+            FloorModel::Concrete => Self::Concrete,
+
+            FloorModel::RoadStraight => Self::RoadStraight,
+            FloorModel::RoadStraightWalkable => Self::RoadStraightWalkable,
+            FloorModel::RoadEnd => Self::RoadEnd,
+            FloorModel::RoadStraightSideOpen => Self::RoadStraightSideOpen,
+            FloorModel::RoadCorner => Self::RoadCorner,
+            FloorModel::RoadCornerWalkable => Self::RoadCornerWalkable,
+            FloorModel::RoadIntersection => Self::RoadIntersection,
+            FloorModel::RoadIntersectionWalkable => Self::RoadIntersectionWalkable,
             _ => panic!("not implemented!"),
         }
     }
@@ -45,8 +50,17 @@ impl From<FloorModel> for PointType {
 impl From<BuildingModel> for PointType {
     fn from(value: BuildingModel) -> Self {
         match value {
-            BuildingModel::Blgd01_01(_) => Self::Blgd01_01,
-            BuildingModel::Blgd02_01(_) => Self::Blgd02_01,
+            BuildingModel::Blgd01_01 => Self::Blgd01_01,
+            BuildingModel::Blgd02_01 => Self::Blgd02_01,
+        }
+    }
+}
+
+impl From<NatureModel> for PointType {
+    fn from(value: NatureModel) -> Self {
+        match value {
+            NatureModel::Tree01 => Self::Tree01,
+            NatureModel::Tree02 => Self::Tree02,
         }
     }
 }
@@ -56,25 +70,61 @@ impl From<Res<'_, ModelCursor>> for PointType {
         match *value {
             ModelCursor::Floor(v) => FloorModel::index(v).into(),
             ModelCursor::Buildings(v) => BuildingModel::index(v).into(),
+            ModelCursor::Nature(v) => NatureModel::index(v).into(),
+            ModelCursor::Zones(_) => panic!("zones can't be placed as points!"),
+            ModelCursor::Terrain(_) => panic!("terrain tools can't be placed as points!"),
         }
     }
 }
 
 impl PointType {
     pub fn meta(&self) -> Meta {
-        if let Ok(a) = FloorModel::try_from(self.clone()) {
-            return a.get_meta().clone();
-        };
+        Catalog::meta(self)
+    }
+
+    pub fn is_road(&self) -> bool {
+        matches!(
+            self,
+            Self::RoadStraight
+                | Self::RoadStraightWalkable
+                | Self::RoadEnd
+                | Self::RoadStraightSideOpen
+                | Self::RoadCorner
+                | Self::RoadCornerWalkable
+                | Self::RoadIntersection
+                | Self::RoadIntersectionWalkable
+                | Self::Bridge(_)
+        )
+    }
 
-        if let Ok(a) = BuildingModel::try_from(self.clone()) {
-            return a.get_meta().clone();
-        };
+    pub fn is_building(&self) -> bool {
+        BuildingModel::try_from(self.clone()).is_ok()
+    }
 
-        panic!("This type does not have meta {:?}", self);
+    pub fn is_nature(&self) -> bool {
+        NatureModel::try_from(self.clone()).is_ok()
     }
+
+    pub fn layer(&self) -> Layer {
+        if self.is_building() {
+            Layer::Building
+        } else if self.is_nature() {
+            Layer::Nature
+        } else {
+            Layer::Floor
+        }
+    }
+}
+
+/// What part of a cell a point takes up, the floor and one thing on top of it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Layer {
+    Floor,
+    Building,
+    Nature,
 }
 
-#[derive(Default, Clone, Deserialize, Serialize, Copy, Debug, Eq, PartialEq)]
+#[derive(Default, Clone, Deserialize, Serialize, Copy, Debug, Eq, PartialEq, Hash)]
 pub struct Position {
     pub x: i32,
     pub y: i32,
@@ -84,6 +134,31 @@ impl Position {
     pub fn new(x: i32, y: i32) -> Self {
         Self { x, y }
     }
+
+    /// The four cells sharing a side with this one, in the order north, east, south, west
+    /// so that the index lines up with `Orientation::index`.
+    pub fn neighbours(&self) -> [Position; 4] {
+        [
+            Self::new(self.x, self.y - 1),
+            Self::new(self.x + 1, self.y),
+            Self::new(self.x, self.y + 1),
+            Self::new(self.x - 1, self.y),
+        ]
+    }
+
+    /// The eight cells around this one, including diagonals.
+    pub fn surrounding(&self) -> [Position; 8] {
+        [
+            Self::new(self.x - 1, self.y - 1),
+            Self::new(self.x, self.y - 1),
+            Self::new(self.x + 1, self.y - 1),
+            Self::new(self.x + 1, self.y),
+            Self::new(self.x + 1, self.y + 1),
+            Self::new(self.x, self.y + 1),
+            Self::new(self.x - 1, self.y + 1),
+            Self::new(self.x - 1, self.y),
+        ]
+    }
 }
 
 #[derive(Component, Clone, Deserialize, Serialize)]
@@ -0,0 +1,104 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use bevy::prelude::*;
+
+use super::{
+    point::{Point, Position},
+    terrain::Terrain,
+    World,
+};
+
+/// Keeps the [`RoadNetwork`] of the whole map up to date for the simulation and the HUD.
+pub struct RoadsPlugin;
+
+impl Plugin for RoadsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(RoadNetwork::default());
+        app.add_systems(PostUpdate, rebuild_network);
+    }
+}
+
+/// Road tiles grouped into networks of side-connected cells.
+///
+/// A network is built from scratch out of the placed points whenever they or the terrain
+/// change, which is cheap enough for the sizes of map we deal with and avoids keeping a
+/// second copy of the roads in sync.
+///
+/// `built_from` The version of the [`World`] the network was built from.
+#[derive(Resource, Default, Debug)]
+pub struct RoadNetwork {
+    networks: HashMap<Position, usize>,
+    sizes: Vec<usize>,
+    built_from: Option<u64>,
+}
+
+impl RoadNetwork {
+    /// A single road tile doesn't lead anywhere, so a network needs at least this many
+    /// tiles before it counts as connected.
+    pub const MIN_CONNECTED_SIZE: usize = 2;
+
+    /// Neighbouring tiles only connect when they meet at the same height, so a road
+    /// running into a cliff is cut off there and one going up a ramp carries on.
+    pub fn from_points<'a>(points: impl IntoIterator<Item = &'a Point>, terrain: &Terrain) -> Self {
+        let roads: Vec<Position> = points
+            .into_iter()
+            .filter(|point| point.has.is_road())
+            .map(|point| point.position)
+            .collect();
+
+        let mut network = Self::default();
+        let mut unvisited: HashSet<Position> = roads.iter().copied().collect();
+
+        for start in roads {
+            if !unvisited.remove(&start) {
+                continue;
+            }
+
+            let id = network.sizes.len();
+            let mut size = 0;
+            let mut queue = VecDeque::from([start]);
+
+            while let Some(pos) = queue.pop_front() {
+                network.networks.insert(pos, id);
+                size += 1;
+
+                for (side, next) in pos.neighbours().into_iter().enumerate() {
+                    if terrain.connects(&pos, side) && unvisited.remove(&next) {
+                        queue.push_back(next);
+                    }
+                }
+            }
+
+            network.sizes.push(size);
+        }
+
+        network
+    }
+
+    pub fn is_road(&self, pos: &Position) -> bool {
+        self.networks.contains_key(pos)
+    }
+
+    /// Amount of tiles in the network `pos` belongs to, `0` if there's no road there.
+    pub fn network_size(&self, pos: &Position) -> usize {
+        match self.networks.get(pos) {
+            Some(id) => self.sizes[*id],
+            None => 0,
+        }
+    }
+
+    pub fn is_connected(&self, pos: &Position) -> bool {
+        self.network_size(pos) >= Self::MIN_CONNECTED_SIZE
+    }
+}
+
+fn rebuild_network(world: Res<World>, terrain: Res<Terrain>, mut network: ResMut<RoadNetwork>) {
+    if network.built_from == Some(world.version()) && !terrain.is_changed() {
+        return;
+    }
+
+    *network = RoadNetwork {
+        built_from: Some(world.version()),
+        ..RoadNetwork::from_points(world.points(), &terrain)
+    };
+}
@@ -0,0 +1,651 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::{
+    math::Ray,
+    prelude::*,
+    render::{mesh::Indices, render_resource::PrimitiveTopology, view::NoFrustumCulling},
+    transform::TransformSystem,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    controls::{
+        input_map::{Action, Actions},
+        model_cursor::ModelCursor,
+        mouse_projection::MouseProjection,
+        place_model::Orientation,
+        walk::CameraView,
+    },
+    hud::PointerOverUi,
+    models::Meta,
+};
+
+use super::{
+    grid::Grid,
+    point::{Point, Position},
+    water::{Water, WaterConfig},
+};
+
+/// Height levels per cell and ramps between them.
+///
+/// With the terrain tools picked with `5`, clicking raises or lowers the cell under the cursor, or
+/// turns it into a ramp up towards the way the cursor is facing. Placed models sit on the
+/// surface of their cell and lean with ramps.
+pub struct TerrainPlugin;
+
+impl Plugin for TerrainPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Terrain::default());
+        app.add_systems(Startup, setup);
+        app.add_systems(Update, edit_terrain.run_if(in_state(CameraView::Orbit)));
+        app.add_systems(
+            PostUpdate,
+            (
+                rebuild_mesh,
+                place_on_terrain.before(TransformSystem::TransformPropagate),
+            ),
+        );
+    }
+}
+
+/// `level` Height of the cell in steps of the grid's `level_height`.
+///
+/// `ramp` Side the cell slopes up towards, reaching one level higher, in the order of
+/// [`Position::neighbours`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Elevation {
+    pub level: i32,
+    #[serde(default)]
+    pub ramp: Option<usize>,
+}
+
+/// A cell of the terrain as it's written to the map save.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerrainCell {
+    pub position: Position,
+    pub level: i32,
+    #[serde(default)]
+    pub ramp: Option<usize>,
+}
+
+/// Only the cells that aren't flat at level `0` are kept.
+#[derive(Resource, Default, Debug)]
+pub struct Terrain {
+    pub cells: HashMap<Position, Elevation>,
+}
+
+/// Direction of each side of a cell on the ground, in the order of [`Position::neighbours`].
+const SIDES: [Vec2; 4] = [
+    Vec2::new(0., -1.),
+    Vec2::new(1., 0.),
+    Vec2::new(0., 1.),
+    Vec2::new(-1., 0.),
+];
+
+impl Terrain {
+    /// Rays give up after marching this far without reaching the surface.
+    const MAX_DISTANCE: f32 = 5000.;
+
+    pub fn get(&self, pos: &Position) -> Elevation {
+        self.cells.get(pos).copied().unwrap_or_default()
+    }
+
+    pub fn set(&mut self, pos: Position, elevation: Elevation) {
+        if elevation == Elevation::default() {
+            self.cells.remove(&pos);
+        } else {
+            self.cells.insert(pos, elevation);
+        }
+    }
+
+    pub fn to_cells(&self) -> Vec<TerrainCell> {
+        self.cells
+            .iter()
+            .map(|(position, elevation)| TerrainCell {
+                position: *position,
+                level: elevation.level,
+                ramp: elevation.ramp,
+            })
+            .collect()
+    }
+
+    pub fn set_cells(&mut self, cells: &[TerrainCell]) {
+        self.cells.clear();
+
+        for cell in cells {
+            self.set(
+                cell.position,
+                Elevation {
+                    level: cell.level,
+                    ramp: cell.ramp,
+                },
+            );
+        }
+    }
+
+    /// Height of the surface at `pos`.
+    pub fn height_at(&self, grid: &Grid, pos: Vec3) -> f32 {
+        let cell = grid.cell_at(pos);
+        let elevation = self.get(&cell);
+        let base = grid.origin.y + elevation.level as f32 * grid.level_height;
+
+        let Some(side) = elevation.ramp else {
+            return base;
+        };
+
+        let local = (pos - grid.cell_center(&cell)) / grid.cell_size;
+        let along = (Vec2::new(local.x, local.z).dot(SIDES[side]) + 0.5).clamp(0., 1.);
+
+        base + along * grid.level_height
+    }
+
+    pub fn normal_at(&self, grid: &Grid, pos: Vec3) -> Vec3 {
+        match self.get(&grid.cell_at(pos)).ramp {
+            Some(side) => {
+                let rise = SIDES[side] * grid.level_height / grid.cell_size;
+
+                Vec3::new(-rise.x, 1., -rise.y).normalize()
+            }
+            None => Vec3::Y,
+        }
+    }
+
+    /// Middle of `cell` on its surface.
+    pub fn cell_center(&self, grid: &Grid, cell: &Position) -> Vec3 {
+        let center = grid.cell_center(cell);
+
+        Vec3::new(center.x, self.height_at(grid, center), center.z)
+    }
+
+    /// Where a model on `cell` goes, raised to its level and leaning up a ramp.
+    pub fn cell_transform(
+        &self,
+        grid: &Grid,
+        cell: &Position,
+        orientation: &Orientation,
+    ) -> Transform {
+        let mut transform = Transform::from_translation(self.cell_center(grid, cell));
+        let facing = Quat::from_rotation_y(orientation.rotation());
+
+        let normal = self.normal_at(grid, transform.translation);
+
+        transform.rotation = Quat::from_rotation_arc(Vec3::Y, normal) * facing;
+        transform
+    }
+
+    /// Level along the `side` of `pos`, `None` when that side is sloped.
+    pub fn edge_level(&self, pos: &Position, side: usize) -> Option<i32> {
+        let elevation = self.get(pos);
+
+        match elevation.ramp {
+            None => Some(elevation.level),
+            Some(up) if up == side => Some(elevation.level + 1),
+            Some(up) if (up + 2) % 4 == side => Some(elevation.level),
+            Some(_) => None,
+        }
+    }
+
+    /// Whether the side of `pos` towards `side` meets its neighbour there at the same
+    /// height, so roads can run across.
+    pub fn connects(&self, pos: &Position, side: usize) -> bool {
+        let next = pos.neighbours()[side];
+
+        match (
+            self.edge_level(pos, side),
+            self.edge_level(&next, (side + 2) % 4),
+        ) {
+            (Some(a), Some(b)) => a == b,
+            _ => false,
+        }
+    }
+
+    /// Lowest and highest heights the surface reaches.
+    fn height_range(&self, grid: &Grid) -> (f32, f32) {
+        let (mut low, mut high) = (0, 0);
+
+        for elevation in self.cells.values() {
+            low = low.min(elevation.level);
+            high = high.max(elevation.level + elevation.ramp.map_or(0, |_| 1));
+        }
+
+        (
+            grid.origin.y + low as f32 * grid.level_height,
+            grid.origin.y + high as f32 * grid.level_height,
+        )
+    }
+
+    /// Distance along `ray` to the surface and the normal there, marching through the
+    /// heights the terrain can be at. `None` when it never reaches the surface.
+    pub fn cast(&self, grid: &Grid, ray: Ray) -> Option<(f32, Vec3)> {
+        let (low, high) = self.height_range(grid);
+        let (origin, direction) = (ray.origin, ray.direction);
+
+        let (start, end) = if direction.y.abs() < f32::EPSILON {
+            if origin.y < low || origin.y > high || low == high {
+                return None;
+            }
+
+            (0., Self::MAX_DISTANCE)
+        } else {
+            let a = (high - origin.y) / direction.y;
+            let b = (low - origin.y) / direction.y;
+
+            let start = a.min(b).max(0.);
+
+            (start, a.max(b).min(start + Self::MAX_DISTANCE))
+        };
+
+        if start > end {
+            return None;
+        }
+
+        let below = |t: f32| {
+            let pos = ray.get_point(t);
+            pos.y <= self.height_at(grid, pos) + 0.01
+        };
+
+        let step = grid.cell_size / 4.;
+        let (mut previous, mut t) = (start, start);
+
+        loop {
+            if below(t) {
+                break;
+            }
+
+            if t >= end {
+                return None;
+            }
+
+            previous = t;
+            t = (t + step).min(end);
+        }
+
+        // Narrow down between the last point above the surface and the first one below.
+        let mut above = previous;
+
+        for _ in 0..12 {
+            if above == t {
+                break;
+            }
+
+            let middle = (above + t) / 2.;
+
+            if below(middle) {
+                t = middle;
+            } else {
+                above = middle;
+            }
+        }
+
+        Some((t, self.normal_at(grid, ray.get_point(t))))
+    }
+}
+
+/// The side of a cell a model turned to `orientation` faces, in the order of [`SIDES`].
+fn side_facing(orientation: &Orientation) -> usize {
+    let facing = Quat::from_rotation_y(orientation.rotation()) * Vec3::NEG_Z;
+    let facing = Vec2::new(facing.x, facing.z);
+
+    (0..SIDES.len())
+        .max_by(|a, b| SIDES[*a].dot(facing).total_cmp(&SIDES[*b].dot(facing)))
+        .unwrap()
+}
+
+/// `Raise` and `Lower` move a cell a level up or down, `Ramp` slopes it up towards the way
+/// the cursor faces, or flattens it again.
+///
+/// `Water` is a brush that floods cells while dragged, see [`super::water`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TerrainTool {
+    Raise,
+    Lower,
+    Ramp,
+    Water,
+}
+
+impl TerrainTool {
+    const INDICES: [Self; 4] = [Self::Raise, Self::Lower, Self::Ramp, Self::Water];
+
+    const MARKER: Meta = Meta::new("./models/roads/road_prop_concrete.glb#Scene0");
+
+    pub fn len() -> usize {
+        Self::INDICES.len()
+    }
+
+    pub fn index(index: usize) -> Self {
+        *Self::INDICES.get(index).unwrap()
+    }
+
+    pub fn meta(&self) -> Meta {
+        Self::MARKER
+    }
+}
+
+#[derive(Component)]
+struct TerrainMesh;
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(Mesh::new(PrimitiveTopology::TriangleList)),
+            material: materials.add(StandardMaterial {
+                base_color: Color::rgb(0.45, 0.36, 0.25),
+                perceptual_roughness: 1.,
+                double_sided: true,
+                cull_mode: None,
+                ..default()
+            }),
+            ..default()
+        },
+        // The bounds are worked out once when the mesh is first filled in and don't grow
+        // with it.
+        NoFrustumCulling,
+        TerrainMesh,
+    ));
+}
+
+fn edit_terrain(
+    cursor: Res<ModelCursor>,
+    actions: Res<Actions>,
+    mouse_projection: Res<MouseProjection>,
+    pointer_over_ui: Res<PointerOverUi>,
+    orientation: Res<Orientation>,
+    grid: Res<Grid>,
+    mut terrain: ResMut<Terrain>,
+) {
+    let ModelCursor::Terrain(index) = *cursor else {
+        return;
+    };
+
+    if pointer_over_ui.0 || !mouse_projection.hit || !grid.contains(&mouse_projection.normal) {
+        return;
+    }
+
+    let place = actions.just_pressed(Action::Place);
+    let remove = actions.just_pressed(Action::Remove);
+
+    if !place && !remove {
+        return;
+    }
+
+    let cell = mouse_projection.normal;
+    let mut elevation = terrain.get(&cell);
+
+    // Removing does the opposite of the tool.
+    match (TerrainTool::index(index), place) {
+        (TerrainTool::Raise, true) | (TerrainTool::Lower, false) => elevation.level += 1,
+        (TerrainTool::Lower, true) | (TerrainTool::Raise, false) => elevation.level -= 1,
+        (TerrainTool::Ramp, true) => elevation.ramp = Some(side_facing(&orientation)),
+        (TerrainTool::Ramp, false) => elevation.ramp = None,
+        (TerrainTool::Water, _) => return,
+    }
+
+    terrain.set(cell, elevation);
+}
+
+/// Keeps placed models on the surface of their cell.
+fn place_on_terrain(
+    grid: Res<Grid>,
+    terrain: Res<Terrain>,
+    mut points: Query<(Ref<Point>, &mut Transform)>,
+) {
+    let everything = grid.is_changed() || terrain.is_changed();
+
+    for (point, mut transform) in points.iter_mut() {
+        if everything || point.is_changed() {
+            *transform = terrain.cell_transform(&grid, &point.position, &point.orientation);
+        }
+    }
+}
+
+/// Builds the cliffs and ramps of the terrain, the tops of the cells are left to the floor
+/// models but get a surface too so bare cells don't show a hole. Flooded cells are carved
+/// down to their bed so the water shows above it.
+fn rebuild_mesh(
+    grid: Res<Grid>,
+    terrain: Res<Terrain>,
+    water: Res<Water>,
+    water_config: Res<WaterConfig>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    terrain_mesh: Query<&Handle<Mesh>, With<TerrainMesh>>,
+) {
+    if !grid.is_changed()
+        && !terrain.is_changed()
+        && !water.is_changed()
+        && !water_config.is_changed()
+    {
+        return;
+    }
+
+    let Some(mesh) = meshes.get_mut(terrain_mesh.single()) else {
+        return;
+    };
+
+    let mut positions: Vec<[f32; 3]> = vec![];
+    let mut normals: Vec<[f32; 3]> = vec![];
+    let mut indices: Vec<u32> = vec![];
+
+    let mut quad = |corners: [Vec3; 4]| {
+        let normal = (corners[1] - corners[0])
+            .cross(corners[3] - corners[0])
+            .normalize_or_zero();
+        let first = positions.len() as u32;
+
+        for corner in corners {
+            positions.push(corner.to_array());
+            normals.push(normal.to_array());
+        }
+
+        indices.extend([first, first + 1, first + 2, first, first + 2, first + 3]);
+    };
+
+    let half = grid.cell_size / 2.;
+
+    // Corners of a cell on the ground, going around it so that corners `i` and `i + 1`
+    // are the two ends of side `i`.
+    let corners = |cell: &Position| {
+        let center = grid.cell_center(cell);
+
+        [
+            center + Vec3::new(-half, 0., -half),
+            center + Vec3::new(half, 0., -half),
+            center + Vec3::new(half, 0., half),
+            center + Vec3::new(-half, 0., half),
+        ]
+    };
+
+    // Sample just inside the cell so corners shared with a neighbour read this cell.
+    let height = |cell: &Position, corner: Vec3| {
+        if water.contains(cell) {
+            return water_config.bed_height(&grid, &terrain, cell);
+        }
+
+        let inside = corner + (grid.cell_center(cell) - corner) * 0.001;
+        terrain.height_at(&grid, inside)
+    };
+
+    let mut cells: HashSet<Position> = HashSet::new();
+
+    for cell in terrain.cells.keys().chain(water.cells.iter()) {
+        cells.insert(*cell);
+        cells.extend(cell.neighbours());
+    }
+
+    for cell in cells.iter() {
+        let ground = corners(cell);
+        let top = ground.map(|corner| corner + Vec3::Y * (height(cell, corner) - corner.y));
+
+        if terrain.cells.contains_key(cell) || water.contains(cell) {
+            // Just under the floor models so they cover it.
+            quad(top.map(|corner| corner - Vec3::Y * 0.05));
+        }
+
+        for (side, next) in cell.neighbours().iter().enumerate() {
+            let (a, b) = (side, (side + 1) % 4);
+
+            let below = [top[a], top[b]].map(|corner| {
+                let floor = height(next, corner).min(corner.y);
+                Vec3::new(corner.x, floor, corner.z)
+            });
+
+            if below[0].y < top[a].y || below[1].y < top[b].y {
+                quad([top[a], top[b], below[1], below[0]]);
+            }
+        }
+    }
+
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.set_indices(Some(Indices::U32(indices)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ramp(level: i32, side: usize) -> Elevation {
+        Elevation {
+            level,
+            ramp: Some(side),
+        }
+    }
+
+    fn flat(level: i32) -> Elevation {
+        Elevation { level, ramp: None }
+    }
+
+    #[test]
+    fn ramps_rise_towards_their_side() {
+        let grid = Grid::default();
+        let mut terrain = Terrain::default();
+        terrain.set(Position::new(0, 0), ramp(1, 1));
+
+        let center = grid.cell_center(&Position::new(0, 0));
+        let half = grid.cell_size / 2. - 0.01;
+
+        let west = terrain.height_at(&grid, center - Vec3::X * half);
+        let middle = terrain.height_at(&grid, center);
+        let east = terrain.height_at(&grid, center + Vec3::X * half);
+
+        assert!((west - grid.level_height).abs() < 0.01);
+        assert!((middle - grid.level_height * 1.5).abs() < 0.01);
+        assert!((east - grid.level_height * 2.).abs() < 0.01);
+    }
+
+    #[test]
+    fn ramps_only_have_level_edges_at_their_ends() {
+        let mut terrain = Terrain::default();
+        terrain.set(Position::new(0, 0), ramp(1, 1));
+
+        assert_eq!(terrain.edge_level(&Position::new(0, 0), 1), Some(2));
+        assert_eq!(terrain.edge_level(&Position::new(0, 0), 3), Some(1));
+        assert_eq!(terrain.edge_level(&Position::new(0, 0), 0), None);
+        assert_eq!(terrain.edge_level(&Position::new(0, 0), 2), None);
+    }
+
+    #[test]
+    fn cliffs_dont_connect() {
+        let mut terrain = Terrain::default();
+        terrain.set(Position::new(0, 0), flat(1));
+        terrain.set(Position::new(0, 1), flat(1));
+
+        // Same level on both sides.
+        assert!(terrain.connects(&Position::new(0, 0), 2));
+        assert!(terrain.connects(&Position::new(0, 1), 0));
+
+        // A level down to the bare ground around them.
+        assert!(!terrain.connects(&Position::new(0, 0), 1));
+        assert!(!terrain.connects(&Position::new(1, 0), 3));
+    }
+
+    #[test]
+    fn ramps_connect_at_both_ends() {
+        let mut terrain = Terrain::default();
+        terrain.set(Position::new(0, 0), ramp(0, 1));
+        terrain.set(Position::new(1, 0), flat(1));
+
+        // Up the ramp onto the raised cell and down onto the ground.
+        assert!(terrain.connects(&Position::new(0, 0), 1));
+        assert!(terrain.connects(&Position::new(0, 0), 3));
+
+        // Not across the slope.
+        assert!(!terrain.connects(&Position::new(0, 0), 0));
+        assert!(!terrain.connects(&Position::new(0, 0), 2));
+    }
+
+    #[test]
+    fn rays_hit_the_top_of_raised_cells() {
+        let grid = Grid::default();
+        let mut terrain = Terrain::default();
+        terrain.set(Position::new(0, 0), flat(2));
+
+        let ray = Ray {
+            origin: Vec3::new(0., 100., 0.),
+            direction: Vec3::NEG_Y,
+        };
+
+        let (distance, normal) = terrain.cast(&grid, ray).unwrap();
+
+        assert!((distance - (100. - grid.level_height * 2.)).abs() < 0.05);
+        assert!(normal.abs_diff_eq(Vec3::Y, 1e-5));
+    }
+
+    #[test]
+    fn rays_hit_the_side_of_cliffs() {
+        let grid = Grid::default();
+        let mut terrain = Terrain::default();
+        terrain.set(Position::new(0, 0), flat(1));
+
+        let ray = Ray {
+            origin: Vec3::new(-40., grid.level_height / 2., 0.),
+            direction: Vec3::X,
+        };
+
+        let (distance, _) = terrain.cast(&grid, ray).unwrap();
+
+        assert!((distance - (40. - grid.cell_size / 2.)).abs() < 0.05);
+    }
+
+    #[test]
+    fn rays_hit_ramps_on_their_slope() {
+        let grid = Grid::default();
+        let mut terrain = Terrain::default();
+        terrain.set(Position::new(0, 0), ramp(0, 1));
+
+        let ray = Ray {
+            origin: Vec3::new(0., 100., 0.),
+            direction: Vec3::NEG_Y,
+        };
+
+        let (distance, normal) = terrain.cast(&grid, ray).unwrap();
+
+        assert!((distance - (100. - grid.level_height / 2.)).abs() < 0.05);
+        assert!(normal.x < 0. && normal.y > 0.);
+    }
+
+    #[test]
+    fn rays_away_from_the_ground_miss() {
+        let grid = Grid::default();
+        let mut terrain = Terrain::default();
+        terrain.set(Position::new(0, 0), flat(1));
+
+        let ray = Ray {
+            origin: Vec3::new(0., 100., 0.),
+            direction: Vec3::Y,
+        };
+
+        assert!(terrain.cast(&grid, ray).is_none());
+    }
+
+    #[test]
+    fn ramps_go_up_the_way_the_cursor_faces() {
+        // East and west turn models towards the neighbour on the other side.
+        for (i, side) in [0, 3, 2, 1].into_iter().enumerate() {
+            assert_eq!(side_facing(&Orientation::index(i)), side);
+        }
+    }
+}
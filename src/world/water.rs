@@ -0,0 +1,370 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::{
+    pbr::NotShadowCaster,
+    prelude::*,
+    reflect::{TypePath, TypeUuid},
+    render::{
+        mesh::Indices,
+        primitives::Aabb,
+        render_resource::{AsBindGroup, PrimitiveTopology, ShaderRef},
+    },
+};
+
+use crate::{
+    controls::{
+        input_map::{Action, Actions},
+        model_cursor::ModelCursor,
+        mouse_projection::MouseProjection,
+        walk::CameraView,
+    },
+    hud::PointerOverUi,
+    models::ModelCache,
+    simulation::zoning::Zones,
+};
+
+use super::{
+    chunks::{sync_world, LoadedChunks},
+    grid::Grid,
+    point::{Point, PointType, Position},
+    terrain::{Terrain, TerrainTool},
+    World,
+};
+
+/// Lakes and rivers, painted with the water brush of the terrain tools.
+///
+/// Flooding a cell clears its floor tiles and zone and turns roads on it into bridges,
+/// cells with buildings or trees on them are left dry. Draining a cell turns its bridges
+/// back into the roads they were.
+pub struct WaterPlugin;
+
+impl Plugin for WaterPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Water::default());
+        app.insert_resource(WaterConfig::default());
+        app.insert_resource(WaterSurfaces::default());
+        app.add_plugins(MaterialPlugin::<WaterMaterial>::default());
+        app.add_systems(Startup, setup);
+        app.add_systems(Update, paint_water.run_if(in_state(CameraView::Orbit)));
+        app.add_systems(Update, update_material);
+        app.add_systems(PostUpdate, rebuild_surfaces.after(sync_world));
+    }
+}
+
+/// The flooded cells.
+#[derive(Resource, Default, Debug)]
+pub struct Water {
+    pub cells: HashSet<Position>,
+}
+
+impl Water {
+    pub fn contains(&self, pos: &Position) -> bool {
+        self.cells.contains(pos)
+    }
+}
+
+/// `depth` How far below the ground of its cell the surface sits.
+///
+/// `bed` How far below the ground of its cell the bottom of a flooded cell is carved.
+///
+/// `wave_height`, `wave_length` and `wave_speed` Shape of the ripples on the surface.
+///
+/// `subdivisions` Quads per side of a cell, more makes the ripples smoother.
+#[derive(Resource, Debug)]
+pub struct WaterConfig {
+    pub depth: f32,
+    pub bed: f32,
+    pub wave_height: f32,
+    pub wave_length: f32,
+    pub wave_speed: f32,
+    pub subdivisions: u32,
+}
+
+impl Default for WaterConfig {
+    fn default() -> Self {
+        Self {
+            depth: 0.6,
+            bed: 1.5,
+            wave_height: 0.15,
+            wave_length: 15.,
+            wave_speed: 4.,
+            subdivisions: 4,
+        }
+    }
+}
+
+impl WaterConfig {
+    /// Height of the surface of `cell` under the ripples, flat at the bottom of the cell so
+    /// water on a ramp doesn't run uphill.
+    pub fn surface_height(&self, grid: &Grid, terrain: &Terrain, cell: &Position) -> f32 {
+        grid.origin.y + terrain.get(cell).level as f32 * grid.level_height - self.depth
+    }
+
+    /// Height the ground of a flooded `cell` is carved down to.
+    pub fn bed_height(&self, grid: &Grid, terrain: &Terrain, cell: &Position) -> f32 {
+        grid.origin.y + terrain.get(cell).level as f32 * grid.level_height - self.bed
+    }
+}
+
+/// The surface, rippled in `shaders/water.wgsl`. The fields are laid out like the uniform
+/// there.
+#[derive(AsBindGroup, TypeUuid, TypePath, Debug, Clone)]
+#[uuid = "3f2c7e9a-58d1-4b6e-9c0a-1e4d7b2a6f35"]
+struct WaterMaterial {
+    #[uniform(0)]
+    color: Color,
+    #[uniform(0)]
+    wave_height: f32,
+    #[uniform(0)]
+    wave_length: f32,
+    #[uniform(0)]
+    wave_speed: f32,
+}
+
+impl Material for WaterMaterial {
+    fn vertex_shader() -> ShaderRef {
+        "shaders/water.wgsl".into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        "shaders/water.wgsl".into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        AlphaMode::Blend
+    }
+}
+
+/// The surface of each spawned chunk, `None` for chunks without water.
+#[derive(Resource, Default)]
+struct WaterSurfaces {
+    material: Handle<WaterMaterial>,
+    built: HashMap<Position, Option<Entity>>,
+}
+
+#[derive(Component)]
+struct WaterSurface;
+
+fn setup(
+    config: Res<WaterConfig>,
+    mut surfaces: ResMut<WaterSurfaces>,
+    mut materials: ResMut<Assets<WaterMaterial>>,
+) {
+    surfaces.material = materials.add(WaterMaterial {
+        color: Color::rgba(0.12, 0.38, 0.58, 0.8),
+        wave_height: config.wave_height,
+        wave_length: config.wave_length,
+        wave_speed: config.wave_speed,
+    });
+}
+
+fn paint_water(
+    mut commands: Commands,
+    cursor: Res<ModelCursor>,
+    mouse_projection: Res<MouseProjection>,
+    actions: Res<Actions>,
+    pointer_over_ui: Res<PointerOverUi>,
+    cache: Res<ModelCache>,
+    grid: Res<Grid>,
+    mut water: ResMut<Water>,
+    mut zones: ResMut<Zones>,
+    mut points: Query<(Entity, &mut Point, Option<&mut Handle<Scene>>)>,
+) {
+    let ModelCursor::Terrain(index) = *cursor else {
+        return;
+    };
+
+    if TerrainTool::index(index) != TerrainTool::Water {
+        return;
+    }
+
+    if pointer_over_ui.0 || !mouse_projection.hit || !grid.contains(&mouse_projection.normal) {
+        return;
+    }
+
+    let cell = mouse_projection.normal;
+
+    if actions.pressed(Action::Remove) {
+        if !water.cells.remove(&cell) {
+            return;
+        }
+
+        for (_, mut point, scene) in points.iter_mut() {
+            if point.position != cell {
+                continue;
+            }
+
+            let PointType::Bridge(road) = &point.has else {
+                continue;
+            };
+
+            point.has = (**road).clone();
+
+            // Batched tiles don't have a scene, their chunk picks up the change.
+            if let Some(mut scene) = scene {
+                *scene = cache.scene(point.has.meta().path);
+            }
+        }
+
+        return;
+    }
+
+    if !actions.pressed(Action::Place) || water.contains(&cell) {
+        return;
+    }
+
+    let blocked = points.iter().any(|(_, point, _)| {
+        point.position == cell && (point.has.is_building() || point.has.is_nature())
+    });
+
+    if blocked {
+        return;
+    }
+
+    for (entity, mut point, scene) in points.iter_mut() {
+        if point.position != cell {
+            continue;
+        }
+
+        if point.has.is_road() {
+            point.has = PointType::Bridge(Box::new(point.has.clone()));
+
+            if let Some(mut scene) = scene {
+                *scene = cache.scene(point.has.meta().path);
+            }
+        } else {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+
+    zones.cells.remove(&cell);
+    water.cells.insert(cell);
+}
+
+/// Keeps the ripples of the material in line with the config.
+fn update_material(
+    config: Res<WaterConfig>,
+    surfaces: Res<WaterSurfaces>,
+    mut materials: ResMut<Assets<WaterMaterial>>,
+) {
+    if !config.is_changed() {
+        return;
+    }
+
+    if let Some(material) = materials.get_mut(&surfaces.material) {
+        material.wave_height = config.wave_height;
+        material.wave_length = config.wave_length;
+        material.wave_speed = config.wave_speed;
+    }
+}
+
+/// Builds the surface of chunks as they're spawned, and of every spawned chunk again when the
+/// water or the ground under it changed. The mesh is flat, the ripples are moved along on the
+/// GPU.
+fn rebuild_surfaces(
+    mut commands: Commands,
+    config: Res<WaterConfig>,
+    grid: Res<Grid>,
+    terrain: Res<Terrain>,
+    water: Res<Water>,
+    loaded: Res<LoadedChunks>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut surfaces: ResMut<WaterSurfaces>,
+) {
+    // The surfaces of unloaded chunks went with the chunk.
+    surfaces.built.retain(|chunk, _| loaded.is_loaded(chunk));
+
+    if water.is_changed() || terrain.is_changed() || grid.is_changed() || config.is_changed() {
+        for entity in surfaces.built.drain().filter_map(|(_, entity)| entity) {
+            commands.entity(entity).despawn();
+        }
+    }
+
+    let missing: Vec<(Position, Entity)> = loaded
+        .roots()
+        .filter(|(chunk, _)| !surfaces.built.contains_key(chunk))
+        .map(|(chunk, root)| (*chunk, *root))
+        .collect();
+
+    if missing.is_empty() {
+        return;
+    }
+
+    let mut flooded: HashMap<Position, Vec<Position>> = HashMap::new();
+
+    for cell in water.cells.iter() {
+        flooded
+            .entry(World::chunk_of(cell))
+            .or_default()
+            .push(*cell);
+    }
+
+    let n = config.subdivisions.max(1);
+    let step = grid.cell_size / n as f32;
+
+    for (chunk, root) in missing {
+        let Some(cells) = flooded.get(&chunk) else {
+            surfaces.built.insert(chunk, None);
+            continue;
+        };
+
+        let mut positions: Vec<[f32; 3]> = vec![];
+        let mut indices: Vec<u32> = vec![];
+
+        for cell in cells {
+            let height = config.surface_height(&grid, &terrain, cell);
+            let corner = grid.cell_center(cell) - Vec3::new(1., 0., 1.) * grid.cell_size / 2.;
+            let first = positions.len() as u32;
+
+            for i in 0..=n {
+                for j in 0..=n {
+                    let x = corner.x + j as f32 * step;
+                    let z = corner.z + i as f32 * step;
+
+                    positions.push([x, height, z]);
+                }
+            }
+
+            for i in 0..n {
+                for j in 0..n {
+                    let a = first + i * (n + 1) + j;
+                    let b = a + n + 1;
+
+                    indices.extend([a, b, a + 1, a + 1, b, b + 1]);
+                }
+            }
+        }
+
+        let (min, max) = positions.iter().fold(
+            (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)),
+            |(min, max), position| {
+                let position = Vec3::from_array(*position);
+                (min.min(position), max.max(position))
+            },
+        );
+
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        mesh.set_indices(Some(Indices::U32(indices)));
+
+        // The ripples go above and below the flat mesh, it's only culled once all of it is out
+        // of view.
+        let reach = Vec3::Y * config.wave_height;
+
+        let entity = commands
+            .spawn((
+                MaterialMeshBundle {
+                    mesh: meshes.add(mesh),
+                    material: surfaces.material.clone(),
+                    ..default()
+                },
+                Aabb::from_min_max(min - reach, max + reach),
+                NotShadowCaster,
+                WaterSurface,
+            ))
+            .id();
+
+        commands.entity(root).add_child(entity);
+        surfaces.built.insert(chunk, Some(entity));
+    }
+}